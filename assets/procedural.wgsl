@@ -24,12 +24,25 @@ fn fragment(
 ) {
     var pbr_input = pbr_input_from_standard_material(in, is_front);
 
+    // Layers the material doesn't declare keep the standard material values
+#ifdef PROCEDURAL_NORMAL
+#ifdef VERTEX_TANGENTS
     pbr_input.N = calculate_tbn_mikktspace(in.world_normal, in.world_tangent) * textureSample(normal_texture, normal_texture_sampler, in.uv, index).xyz;
+#endif
+#endif
+#ifdef PROCEDURAL_DIFFUSE
     pbr_input.material.base_color *= textureSample(color_texture, color_texture_sampler, in.uv, index);
+#endif
+#ifdef PROCEDURAL_EMISSIVE
     pbr_input.material.emissive *= textureSample(emissive_texture, emissive_texture_sampler, in.uv, index);
+#endif
     pbr_input.material.emissive += add_emission;
+#ifdef PROCEDURAL_METALLIC
     pbr_input.material.metallic = textureSample(metallic_texture, metallic_texture_sampler, in.uv, index).r;
+#endif
+#ifdef PROCEDURAL_ROUGHNESS
     pbr_input.material.perceptual_roughness = textureSample(roughness_texture, roughness_texture_sampler, in.uv, index).r;
+#endif
     pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
 
     var color = apply_pbr_lighting(pbr_input);
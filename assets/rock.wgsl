@@ -12,7 +12,6 @@ struct RoomFloorMaterial {
 }
 
 @group(0) @binding(0) var out_color: texture_storage_2d_array<rgba8unorm, write>;
-@group(0) @binding(1) var out_emissive: texture_storage_2d_array<rgba16float, write>;
 @group(0) @binding(2) var out_metallic: texture_storage_2d_array<r8unorm, write>;
 @group(0) @binding(3) var out_roughness: texture_storage_2d_array<r8unorm, write>;
 @group(0) @binding(4) var out_normal: texture_storage_2d_array<rgba8unorm, write>;
@@ -39,6 +38,12 @@ fn normal(uv: vec2<f32>, delta: vec2<f32>, seed: f32) -> vec3<f32> {
     return normalize(vec3<f32>(dl - dr, db - dt, 2.0));
 }
 
+// Invocations cover the largest layer, a smaller layer is written at the
+// texel its own size puts `uv` on
+fn layer_coord(uv: vec2<f32>, dims: vec2<u32>) -> vec2<i32> {
+    return vec2<i32>(uv * vec2<f32>(dims));
+}
+
 fn hsv2rgb(c: vec3<f32>) -> vec3<f32> {
     const K = vec4<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
     let p = abs(fract(c.xxx + K.xyz) * 6.0 - K.www);
@@ -67,9 +72,6 @@ fn fragment(in: VertexOutput) {
     let material = material[in.index];
 
     let delta = dpdx(in.uv) + dpdy(in.uv);
-    let size = globals.texture_size;
-    let frag_coord = vec2<i32>(in.uv * size);
-
     let seed = material.seed;
 
     let depth = smoothstep(0.1, 0.9, depth(in.uv, seed));
@@ -79,13 +81,11 @@ fn fragment(in: VertexOutput) {
     let ice = vec3<f32>(0.4, 0.35, 0.3);
 
     let color = vec4<f32>(depth * silicon, 1.0);
-    // let emissive = vec4<f32>(br * hsv2rgb(vec3<f32>(fract(material.seed), 1.0, 1.0)) * 5.0, 1.0);
     let metallic = depth;
     let roughness = 1.0 - depth;
 
-    textureStore(out_color, frag_coord, in.index, color);
-    // textureStore(out_emissive, frag_coord, in.index, emissive);
-    textureStore(out_metallic, frag_coord, in.index, vec4<f32>(metallic, 0.0, 0.0, 0.0));
-    textureStore(out_roughness, frag_coord, in.index, vec4<f32>(roughness, 0.0, 0.0, 0.0));
-    textureStore(out_normal, frag_coord, in.index, vec4<f32>(normal, 0.0));
+    textureStore(out_color, layer_coord(in.uv, textureDimensions(out_color)), in.index, color);
+    textureStore(out_metallic, layer_coord(in.uv, textureDimensions(out_metallic)), in.index, vec4<f32>(metallic, 0.0, 0.0, 0.0));
+    textureStore(out_roughness, layer_coord(in.uv, textureDimensions(out_roughness)), in.index, vec4<f32>(roughness, 0.0, 0.0, 0.0));
+    textureStore(out_normal, layer_coord(in.uv, textureDimensions(out_normal)), in.index, vec4<f32>(normal, 0.0));
 }
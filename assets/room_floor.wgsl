@@ -1,4 +1,3 @@
-#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
 #import noisy_bevy::fbm_simplex_3d
 
 struct ProceduralMaterialGlobals {
@@ -20,6 +19,7 @@ struct RoomFloorMaterial {
 @group(0) @binding(4) var out_normal: texture_storage_2d_array<rgba8unorm, write>;
 @group(0) @binding(5) var<uniform> globals: ProceduralMaterialGlobals;
 @group(0) @binding(100) var<storage, read> material: array<RoomFloorMaterial>;
+@group(0) @binding(101) var<storage, read> dirty: array<u32>;
 
 fn max2(in: vec2<f32>) -> f32 {
     return max(in.x, in.y);
@@ -61,49 +61,44 @@ fn normal(uv: vec2<f32>, delta: vec2<f32>, size: vec2<f32>) -> vec3<f32> {
     return normalize(vec3<f32>(dl - dr, db - dt, 2.0));
 }
 
+// Invocations cover the largest layer, a smaller layer is written at the
+// texel its own size puts `uv` on
+fn layer_coord(uv: vec2<f32>, dims: vec2<u32>) -> vec2<i32> {
+    return vec2<i32>(uv * vec2<f32>(dims));
+}
+
 fn hsv2rgb(c: vec3<f32>) -> vec3<f32> {
     const K = vec4<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
     let p = abs(fract(c.xxx + K.xyz) * 6.0 - K.www);
     return c.z * mix(K.xxx, clamp(p - K.xxx, vec3<f32>(0.0), vec3<f32>(1.0)), c.y);
 }
 
-struct VertexOutput {
-    @builtin(position)
-    position: vec4<f32>,
-    @location(0)
-    uv: vec2<f32>,
-    @location(1)
-    index: u32,
-};
-
-@vertex
-fn vertex(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance_index: u32) -> VertexOutput {
-    let uv = vec2<f32>(f32(vertex_index >> 1u), f32(vertex_index & 1u)) * 2.0;
-    let clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
-    return VertexOutput(clip_position, uv, instance_index);
-}
+@compute @workgroup_size(8, 8, 1)
+fn compute(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = globals.texture_size;
+    if f32(id.x) >= size.x || f32(id.y) >= size.y {
+        return;
+    }
 
-@fragment
-fn fragment(in: VertexOutput) {
-    let material = material[in.index];
+    let index = dirty[id.z];
+    let material = material[index];
 
-    let delta = dpdx(in.uv) + dpdy(in.uv);
-    let size = globals.texture_size;
-    let frag_coord = vec2<i32>(in.uv * size);
+    let uv = (vec2<f32>(id.xy) + 0.5) / size;
+    let delta = 1.0 / size;
 
-    let depth = depth(in.uv, size, 0.0);
-    let normal = normal(in.uv, delta, size);
+    let depth = depth(uv, size, 0.0);
+    let normal = normal(uv, delta, size);
     let facing = smoothstep(0.9, 1.0, depth);
-    let br = facing * smoothstep(material.low_edge, material.high_edge, fbm_simplex_3d(vec3<f32>(floor(in.uv * 12.0), material.seed + material.time * 0.01), 2, 2.0, 2.0, false));
+    let br = facing * smoothstep(material.low_edge, material.high_edge, fbm_simplex_3d(vec3<f32>(floor(uv * 12.0), material.seed + material.time * 0.01), 2, 2.0, 2.0, false));
 
     let color = vec4<f32>(vec3<f32>(0.0), 1.0);
     let emissive = vec4<f32>(br * hsv2rgb(vec3<f32>(fract(material.seed), 1.0, 1.0)) * 5.0, 1.0);
     let metallic = facing;
     let roughness = 1.0;
 
-    textureStore(out_color, frag_coord, in.index, color);
-    textureStore(out_emissive, frag_coord, in.index, emissive);
-    textureStore(out_metallic, frag_coord, in.index, vec4<f32>(metallic, 0.0, 0.0, 0.0));
-    textureStore(out_roughness, frag_coord, in.index, vec4<f32>(roughness, 0.0, 0.0, 0.0));
-    textureStore(out_normal, frag_coord, in.index, vec4<f32>(normal, 0.0));
+    textureStore(out_color, layer_coord(uv, textureDimensions(out_color)), index, color);
+    textureStore(out_emissive, layer_coord(uv, textureDimensions(out_emissive)), index, emissive);
+    textureStore(out_metallic, layer_coord(uv, textureDimensions(out_metallic)), index, vec4<f32>(metallic, 0.0, 0.0, 0.0));
+    textureStore(out_roughness, layer_coord(uv, textureDimensions(out_roughness)), index, vec4<f32>(roughness, 0.0, 0.0, 0.0));
+    textureStore(out_normal, layer_coord(uv, textureDimensions(out_normal)), index, vec4<f32>(normal, 0.0));
 }
@@ -3,14 +3,21 @@ use bevy::prelude::*;
 use collisions::CollisionsPlugin;
 use game_button::GameButtonPlugin;
 use material_modifier::MaterialModifierPlugin;
+use mipmaps::MipmapGeneratorPlugin;
+use sfx::SfxPlugin;
+use update_material_textures::UpdateMaterialTexturesPlugin;
 
 pub mod background;
 pub mod mouse_event;
 pub mod collisions;
 pub mod game_button;
+pub mod input_map;
 pub mod material_modifier;
+pub mod mipmaps;
 pub mod procedural_material;
 pub mod music_player;
+pub mod sfx;
+pub mod update_material_textures;
 
 pub struct ComponentsPlugin;
 
@@ -19,6 +26,9 @@ impl Plugin for ComponentsPlugin {
         app.add_plugins(BackgroundPlugin)
             .add_plugins(CollisionsPlugin)
             .add_plugins(GameButtonPlugin)
-            .add_plugins(MaterialModifierPlugin::<StandardMaterial, StandardMaterial>::default());
+            .add_plugins(SfxPlugin)
+            .add_plugins(MipmapGeneratorPlugin)
+            .add_plugins(MaterialModifierPlugin::<StandardMaterial, StandardMaterial>::default())
+            .add_plugins(UpdateMaterialTexturesPlugin::<StandardMaterial>::default());
     }
 }
@@ -27,6 +27,7 @@ use bevy::{
 #[derive(Resource, ExtractResource, Reflect, Clone)]
 pub struct BackgroundPluginSettings {
     pub shader: String,
+    pub seed: f32,
 }
 
 pub struct BackgroundPlugin;
@@ -150,6 +151,7 @@ struct BackgroundPipeline {
 impl FromWorld for BackgroundPipeline {
     fn from_world(world: &mut World) -> Self {
         let settings = world.resource::<BackgroundPluginSettings>();
+        let seed = settings.seed;
 
         let render_device = world.resource::<RenderDevice>();
         let layout = render_device.create_bind_group_layout(
@@ -189,7 +191,7 @@ impl FromWorld for BackgroundPipeline {
         Self {
             layout,
             pipeline_id,
-            seed: rand::random(),
+            seed,
         }
     }
 }
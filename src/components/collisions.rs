@@ -1,6 +1,6 @@
 use bevy::{
+    ecs::entity::{EntityHashMap, EntityHashSet},
     prelude::*,
-    utils::{HashMap, HashSet},
 };
 use bevy_rapier2d::prelude::*;
 
@@ -15,12 +15,12 @@ impl Plugin for CollisionsPlugin {
 
 #[derive(Resource, Default)]
 pub struct Collisions {
-    map: HashMap<Entity, HashSet<Entity>>,
-    dummy: HashSet<Entity>,
+    map: EntityHashMap<EntityHashSet>,
+    dummy: EntityHashSet,
 }
 
 impl Collisions {
-    pub fn get(&self, entity: Entity) -> &HashSet<Entity> {
+    pub fn get(&self, entity: Entity) -> &EntityHashSet {
         self.map.get(&entity).unwrap_or(&self.dummy)
     }
 }
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 
-use super::mouse_event::Clicked;
+use super::{
+    mouse_event::Clicked,
+    sfx::{PlaySfx, Sfx},
+};
 
 pub struct GameButtonPlugin;
 
@@ -104,6 +107,7 @@ fn animate(
             Interaction::Pressed => {
                 if interaction.is_changed() {
                     commands.trigger_targets(Clicked, entity);
+                    commands.trigger(PlaySfx(Sfx::Click));
                 }
                 (game_button.timer + speed * time.delta_secs()).min(2.0)
             }
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, Serialize, Deserialize,
+)]
+pub enum Action {
+    Pause,
+    Destroy,
+    Cancel,
+    Interact,
+    Priority,
+    Repair,
+    Focus,
+    Stats,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    PanDrag,
+    ZoomIn,
+    ZoomOut,
+    Hotbar1,
+    Hotbar2,
+    Hotbar3,
+    Hotbar4,
+    Hotbar5,
+    Hotbar6,
+    Hotbar7,
+    Hotbar8,
+}
+
+impl Action {
+    pub const HOTBAR: [Action; 8] = [
+        Action::Hotbar1,
+        Action::Hotbar2,
+        Action::Hotbar3,
+        Action::Hotbar4,
+        Action::Hotbar5,
+        Action::Hotbar6,
+        Action::Hotbar7,
+        Action::Hotbar8,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pause => "Pause",
+            Self::Destroy => "Destroy mode",
+            Self::Cancel => "Cancel",
+            Self::Interact => "Build / interact",
+            Self::Priority => "Power priority",
+            Self::Repair => "Repair",
+            Self::Focus => "Focus camera",
+            Self::Stats => "Production stats",
+            Self::PanUp => "Pan up",
+            Self::PanDown => "Pan down",
+            Self::PanLeft => "Pan left",
+            Self::PanRight => "Pan right",
+            Self::PanDrag => "Drag camera",
+            Self::ZoomIn => "Zoom in",
+            Self::ZoomOut => "Zoom out",
+            Self::Hotbar1 => "Hotbar 1",
+            Self::Hotbar2 => "Hotbar 2",
+            Self::Hotbar3 => "Hotbar 3",
+            Self::Hotbar4 => "Hotbar 4",
+            Self::Hotbar5 => "Hotbar 5",
+            Self::Hotbar6 => "Hotbar 6",
+            Self::Hotbar7 => "Hotbar 7",
+            Self::Hotbar8 => "Hotbar 8",
+        }
+    }
+
+    fn default_bindings(&self) -> Vec<Binding> {
+        use Binding::{Key, Mouse};
+        match self {
+            Self::Pause => vec![Key(KeyCode::Escape)],
+            Self::Destroy => vec![Key(KeyCode::KeyX)],
+            Self::Cancel => vec![Mouse(MouseButton::Right)],
+            Self::Interact => vec![Mouse(MouseButton::Left)],
+            Self::Priority => vec![Key(KeyCode::KeyP)],
+            Self::Repair => vec![Key(KeyCode::KeyR)],
+            Self::Focus => vec![Key(KeyCode::KeyF)],
+            Self::Stats => vec![Key(KeyCode::Tab)],
+            Self::PanUp => vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)],
+            Self::PanDown => vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)],
+            Self::PanLeft => vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)],
+            Self::PanRight => vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)],
+            Self::PanDrag => vec![Mouse(MouseButton::Middle)],
+            Self::ZoomIn => vec![Key(KeyCode::Equal)],
+            Self::ZoomOut => vec![Key(KeyCode::Minus)],
+            Self::Hotbar1 => vec![Key(KeyCode::Digit1)],
+            Self::Hotbar2 => vec![Key(KeyCode::Digit2)],
+            Self::Hotbar3 => vec![Key(KeyCode::Digit3)],
+            Self::Hotbar4 => vec![Key(KeyCode::Digit4)],
+            Self::Hotbar5 => vec![Key(KeyCode::Digit5)],
+            Self::Hotbar6 => vec![Key(KeyCode::Digit6)],
+            Self::Hotbar7 => vec![Key(KeyCode::Digit7)],
+            Self::Hotbar8 => vec![Key(KeyCode::Digit8)],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Key(key) => {
+                let name = format!("{key:?}");
+                ["Key", "Digit", "Arrow"]
+                    .iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .unwrap_or(&name)
+                    .to_string()
+            }
+            Self::Mouse(MouseButton::Left) => "LMB".to_string(),
+            Self::Mouse(MouseButton::Right) => "RMB".to_string(),
+            Self::Mouse(MouseButton::Middle) => "MMB".to_string(),
+            Self::Mouse(button) => format!("Mouse {button:?}"),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        }
+    }
+}
+
+impl InputMap {
+    // Actions missing from an older settings file, or left without any binding,
+    // fall back to their defaults
+    pub fn validated(mut self) -> Self {
+        for action in Action::iter() {
+            let bindings = self.bindings.entry(action).or_default();
+            if bindings.is_empty() {
+                *bindings = action.default_bindings();
+            }
+        }
+        self
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn label(&self, action: Action) -> String {
+        self.bindings(action)
+            .iter()
+            .map(Binding::name)
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    // An action losing its only binding takes over the rebound action's old ones,
+    // so nothing ends up unbound
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let previous = self
+            .bindings
+            .insert(action, vec![binding])
+            .unwrap_or_default();
+        for (other, bindings) in &mut self.bindings {
+            if *other == action || !bindings.contains(&binding) {
+                continue;
+            }
+            bindings.retain(|b| *b != binding);
+            if bindings.is_empty() {
+                *bindings = previous.clone();
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+    input_map: Res<'w, InputMap>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+}
+
+impl Actions<'_> {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.input_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => self.keyboard.pressed(*key),
+                Binding::Mouse(button) => self.mouse.pressed(*button),
+            })
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.input_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => self.keyboard.just_pressed(*key),
+                Binding::Mouse(button) => self.mouse.just_pressed(*button),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stolen_binding_swaps_with_the_rebound_action() {
+        let mut input_map = InputMap::default();
+        input_map.bind(Action::Hotbar1, Binding::Key(KeyCode::Escape));
+
+        assert_eq!(
+            input_map.bindings(Action::Hotbar1),
+            &[Binding::Key(KeyCode::Escape)]
+        );
+        assert_eq!(
+            input_map.bindings(Action::Pause),
+            &[Binding::Key(KeyCode::Digit1)]
+        );
+    }
+
+    #[test]
+    fn empty_bindings_are_restored_on_load() {
+        let mut input_map = InputMap::default();
+        input_map.bindings.insert(Action::Pause, vec![]);
+        input_map.bindings.remove(&Action::Repair);
+
+        let input_map = input_map.validated();
+        assert_eq!(
+            input_map.bindings(Action::Pause),
+            &[Binding::Key(KeyCode::Escape)]
+        );
+        assert_eq!(
+            input_map.bindings(Action::Repair),
+            &[Binding::Key(KeyCode::KeyR)]
+        );
+    }
+}
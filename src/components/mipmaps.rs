@@ -7,7 +7,6 @@ use bevy::{
     image::{ImageFilterMode, ImageSampler, ImageSamplerDescriptor, TextureFormatPixelInfo},
     prelude::*,
     render::{
-        extract_component::{ExtractComponent, ExtractComponentPlugin},
         render_asset::RenderAssets,
         render_graph::{Node, NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel},
         render_resource::{
@@ -15,14 +14,16 @@ use bevy::{
             BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, CachedRenderPipelineId,
             ColorTargetState, ColorWrites, FilterMode, FragmentState, MultisampleState, Operations,
             PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
-            RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages,
-            SpecializedRenderPipeline, SpecializedRenderPipelines, TextureFormat,
-            TextureSampleType, TextureViewDescriptor,
+            RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType,
+            SamplerDescriptor, ShaderStages, SpecializedRenderPipeline, SpecializedRenderPipelines,
+            Texture, TextureFormat, TextureSampleType, TextureUsages, TextureViewDescriptor,
+            TextureViewDimension,
         },
         renderer::{RenderContext, RenderDevice},
-        sync_world::MainEntity,
+        sync_component::SyncComponentPlugin,
+        sync_world::{MainEntity, RenderEntity},
         texture::GpuImage,
-        MainWorld, RenderApp,
+        Extract, MainWorld, RenderApp,
     },
     utils::{hashbrown::HashMap, HashSet},
 };
@@ -40,7 +41,7 @@ enum GenerateMipsState {
     Rendered,
 }
 
-#[derive(Component, ExtractComponent, Clone, Debug)]
+#[derive(Component, Clone, Debug)]
 pub struct GenerateMips {
     image: AssetId<Image>,
     mode: GenerateMipsMode,
@@ -65,7 +66,7 @@ pub struct MipmapGeneratorPlugin;
 
 impl Plugin for MipmapGeneratorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ExtractComponentPlugin::<GenerateMips>::default())
+        app.add_plugins(SyncComponentPlugin::<GenerateMips>::default())
             .add_systems(
                 PostUpdate,
                 (cleanup_states, init_mip_textures.after(cleanup_states)),
@@ -76,10 +77,12 @@ impl Plugin for MipmapGeneratorPlugin {
         };
 
         render_app
-            .add_systems(ExtractSchedule, readback_states)
+            // The state the node left behind last frame has to reach the main
+            // world before it's extracted again, or `Once` mips would be redone
+            .add_systems(ExtractSchedule, (readback_states, extract_states).chain())
             .add_systems(ExtractSchedule, extract_pipelines)
             .add_render_graph_node::<MipmapGeneratorNode>(Core3d, MipmapGeneratorLabel)
-            .add_render_graph_edges(Core3d, (Node3d::Prepass, MipmapGeneratorLabel));
+            .add_render_graph_edges(Core3d, (MipmapGeneratorLabel, Node3d::Prepass));
     }
 
     fn finish(&self, app: &mut App) {
@@ -100,34 +103,40 @@ fn cleanup_states(
 ) {
     for (entity, state) in states.iter() {
         if !images.contains(state.image) {
-            println!("Despawning");
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
+// Number of levels in a full mip chain down to 1x1
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+// Allocates the mip levels, they are rendered on the GPU once the image is uploaded
 fn init_mip_textures(mut images: ResMut<Assets<Image>>, mut states: Query<&mut GenerateMips>) {
     for mut state in states.iter_mut() {
-        if let GenerateMipsState::Spawned = state.state {
-            state.state = GenerateMipsState::Ready;
-        } else {
+        let GenerateMipsState::Spawned = state.state else {
             continue;
-        }
-
-        let image = images.get_mut(state.image).unwrap();
+        };
+        // Images that are still loading are picked up on a later frame
+        let Some(image) = images.get_mut(state.image) else {
+            continue;
+        };
+        state.state = GenerateMipsState::Ready;
 
         let (width, height) = {
             let size = image.size();
             (size.x, size.y)
         };
 
-        let mips = 1 + (width.max(height) as f32).log2() as u32;
+        let mips = mip_level_count(width, height);
 
         let mut bytes = 0;
         for level in 0..mips {
             bytes += image.texture_descriptor.format.pixel_size()
-                * (width >> level) as usize
-                * (height >> level) as usize;
+                * (width >> level).max(1) as usize
+                * (height >> level).max(1) as usize;
         }
 
         image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
@@ -137,7 +146,8 @@ fn init_mip_textures(mut images: ResMut<Assets<Image>>, mut states: Query<&mut G
             ..Default::default()
         });
         image.texture_descriptor.mip_level_count = mips;
-        image.data.resize(bytes, 16);
+        image.texture_descriptor.usage |= TextureUsages::RENDER_ATTACHMENT;
+        image.data.resize(bytes, 0);
     }
 }
 
@@ -149,6 +159,14 @@ fn readback_states(mut world: ResMut<MainWorld>, states: Query<(MainEntity, &Gen
     }
 }
 
+fn extract_states(mut commands: Commands, states: Extract<Query<(RenderEntity, &GenerateMips)>>) {
+    let states = states
+        .iter()
+        .map(|(entity, state)| (entity, state.clone()))
+        .collect::<Vec<_>>();
+    commands.insert_or_spawn_batch(states);
+}
+
 #[derive(Component, Clone)]
 struct MipmapPipelineId(CachedRenderPipelineId);
 
@@ -161,7 +179,9 @@ fn extract_pipelines(
     states: Query<(Entity, &GenerateMips), Without<MipmapPipelineId>>,
 ) {
     for (entity, state) in states.iter() {
-        let image = images.get(state.image).unwrap();
+        let Some(image) = images.get(state.image) else {
+            continue;
+        };
         commands
             .entity(entity)
             .insert(MipmapPipelineId(pipelines.specialize(
@@ -225,44 +245,68 @@ impl Node for MipmapGeneratorNode {
         let images = world.resource::<RenderAssets<GpuImage>>();
 
         for (image, pipeline_id) in &self.images {
-            let image = images.get(*image).unwrap();
-            let pipeline = pipeline_cache.get_render_pipeline(*pipeline_id).unwrap();
+            let Some(image) = images.get(*image) else {
+                continue;
+            };
+            let Some(pipeline) = pipeline_cache.get_render_pipeline(*pipeline_id) else {
+                continue;
+            };
+            render_mips(
+                render_context,
+                mip_gen_pipeline,
+                pipeline,
+                &image.texture,
+                0..image.texture.depth_or_array_layers(),
+            );
+        }
 
-            for mip in 1..image.texture.mip_level_count() {
-                let view_prev = image.texture.create_view(&TextureViewDescriptor {
-                    base_mip_level: mip - 1,
-                    mip_level_count: Some(1),
-                    ..Default::default()
-                });
-                let view_cur = image.texture.create_view(&TextureViewDescriptor {
+        Ok(())
+    }
+}
+
+// Fills every mip level of the given array layers by downsampling the level
+// above, the texture needs TEXTURE_BINDING and RENDER_ATTACHMENT usages
+pub fn render_mips(
+    render_context: &mut RenderContext,
+    mip_gen_pipeline: &MipmapGeneratorPipeline,
+    pipeline: &RenderPipeline,
+    texture: &Texture,
+    layers: impl IntoIterator<Item = u32>,
+) {
+    for layer in layers {
+        for mip in 1..texture.mip_level_count() {
+            let view = |mip| {
+                texture.create_view(&TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::D2),
                     base_mip_level: mip,
                     mip_level_count: Some(1),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
                     ..Default::default()
-                });
-                let bind_group = render_context.render_device().create_bind_group(
-                    "mip_gen_bind_group",
-                    &mip_gen_pipeline.texture_bind_group,
-                    &BindGroupEntries::sequential((&view_prev, &mip_gen_pipeline.sampler)),
-                );
-                let mut render_pass =
-                    render_context.begin_tracked_render_pass(RenderPassDescriptor {
-                        label: Some("mip_gen_pass"),
-                        color_attachments: &[Some(RenderPassColorAttachment {
-                            view: &view_cur,
-                            resolve_target: None,
-                            ops: Operations::default(),
-                        })],
-                        depth_stencil_attachment: None,
-                        timestamp_writes: None,
-                        occlusion_query_set: None,
-                    });
-                render_pass.set_render_pipeline(pipeline);
-                render_pass.set_bind_group(0, &bind_group, &[]);
-                render_pass.draw(0..3, 0..1);
-            }
+                })
+            };
+            let view_prev = view(mip - 1);
+            let view_cur = view(mip);
+            let bind_group = render_context.render_device().create_bind_group(
+                "mip_gen_bind_group",
+                &mip_gen_pipeline.texture_bind_group,
+                &BindGroupEntries::sequential((&view_prev, &mip_gen_pipeline.sampler)),
+            );
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("mip_gen_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view_cur,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_render_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
         }
-
-        Ok(())
     }
 }
 
@@ -1,37 +1,229 @@
-use bevy::{audio::PlaybackMode, prelude::*};
-use rand::seq::IteratorRandom;
+use bevy::{
+    asset::LoadedFolder,
+    audio::{PlaybackMode, Source},
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+use rand::seq::SliceRandom;
 
 use crate::scenes::{AppSceneRoot, AppState};
 
+use super::sfx::{AudioChannel, AudioGain};
+
+const CROSSFADE: f32 = 3.0;
+
 pub struct MusicPlayerPlugin;
 
 impl Plugin for MusicPlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PreUpdate, update.run_if(in_state(AppState::Game)));
+        app.init_resource::<MusicPlaylist>()
+            .add_systems(Startup, load)
+            .add_systems(PreUpdate, collect_tracks)
+            .add_systems(PreUpdate, update.run_if(in_state(AppState::Game)));
+    }
+}
+
+#[derive(Event)]
+pub struct NowPlaying(pub String);
+
+struct Track {
+    handle: Handle<AudioSource>,
+    name: String,
+    duration: Option<f32>,
+    task: Option<Task<Option<f32>>>,
+}
+
+#[derive(Resource, Default)]
+pub struct MusicPlaylist {
+    folder: Handle<LoadedFolder>,
+    tracks: Vec<Track>,
+    queue: Vec<usize>,
+    last: Option<usize>,
+    loaded: bool,
+}
+
+impl MusicPlaylist {
+    // Each track plays once per round, and a new round never repeats the last track
+    fn next(&mut self) -> Option<usize> {
+        if self.queue.is_empty() {
+            self.queue = (0..self.tracks.len()).collect();
+            self.queue.shuffle(&mut rand::rng());
+            if self.queue.len() > 1 && self.queue.last() == self.last.as_ref() {
+                self.queue.swap(0, self.tracks.len() - 1);
+            }
+        }
+        self.last = self.queue.pop();
+        self.last
     }
 }
 
 #[derive(Component)]
-struct Music;
+struct Music {
+    track: usize,
+    elapsed: f32,
+    fading_out: bool,
+}
+
+fn load(asset_server: Res<AssetServer>, mut playlist: ResMut<MusicPlaylist>) {
+    playlist.folder = asset_server.load_folder("music");
+}
+
+fn collect_tracks(
+    asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
+    sources: Res<Assets<AudioSource>>,
+    mut playlist: ResMut<MusicPlaylist>,
+) {
+    if !playlist.loaded {
+        if asset_server.load_state(&playlist.folder).is_failed() {
+            warn!("No music folder, playing without music");
+            playlist.loaded = true;
+            return;
+        }
+        let Some(folder) = folders.get(&playlist.folder) else {
+            return;
+        };
+        let mut tracks = folder
+            .handles
+            .iter()
+            .filter_map(|handle| handle.clone().try_typed::<AudioSource>().ok())
+            .map(|handle| Track {
+                name: handle
+                    .path()
+                    .and_then(|path| path.path().file_stem())
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                handle,
+                duration: None,
+                task: None,
+            })
+            .collect::<Vec<_>>();
+        tracks.sort_by(|a, b| a.name.cmp(&b.name));
+        if tracks.is_empty() {
+            warn!("Music folder is empty, playing without music");
+        }
+        playlist.tracks = tracks;
+        playlist.loaded = true;
+    }
+
+    // Formats without a duration in their header are decoded in the background
+    for track in playlist.tracks.iter_mut() {
+        if track.duration.is_some() {
+            continue;
+        }
+        match &mut track.task {
+            None => {
+                if let Some(source) = sources.get(&track.handle) {
+                    if let Some(duration) = source.decoder().total_duration() {
+                        track.duration = Some(duration.as_secs_f32());
+                        continue;
+                    }
+                    let source = source.clone();
+                    track.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+                        let decoder = source.decoder();
+                        let rate = decoder.sample_rate() as f32 * decoder.channels() as f32;
+                        (rate > 0.0).then(|| decoder.count() as f32 / rate)
+                    }));
+                }
+            }
+            Some(task) => {
+                if let Some(duration) = block_on(future::poll_once(task)) {
+                    track.duration = Some(duration.unwrap_or(f32::INFINITY));
+                    track.task = None;
+                }
+            }
+        }
+    }
+}
 
 fn update(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    music: Query<(), With<Music>>,
+    mut playlist: ResMut<MusicPlaylist>,
+    mut music: Query<(Entity, &mut Music, &mut AudioGain)>,
     root_entity: Res<AppSceneRoot>,
+    time: Res<Time>,
 ) {
-    if music.is_empty() {
-        let files = std::fs::read_dir("./assets/music").unwrap();
-        let file = files.choose(&mut rand::rng()).unwrap().unwrap();
-        commands.entity(root_entity.world).with_child((
-            Music,
-            AudioPlayer::new(
-                asset_server.load(format!("music/{}", file.file_name().into_string().unwrap())),
-            ),
-            PlaybackSettings {
-                mode: PlaybackMode::Despawn,
-                ..Default::default()
-            },
-        ));
+    let mut playing = false;
+    for (entity, mut music, mut gain) in music.iter_mut() {
+        music.elapsed += time.delta_secs();
+        let duration = playlist.tracks[music.track].duration;
+        let remaining = duration.map_or(f32::INFINITY, |d| d - music.elapsed);
+
+        if remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if remaining <= CROSSFADE {
+            music.fading_out = true;
+        }
+
+        gain.0 = (music.elapsed / CROSSFADE)
+            .min(remaining / CROSSFADE)
+            .min(1.0);
+        playing |= !music.fading_out;
+    }
+
+    if playing || !playlist.loaded {
+        return;
+    }
+
+    let Some(track) = playlist.next() else {
+        return;
+    };
+
+    commands.trigger(NowPlaying(playlist.tracks[track].name.clone()));
+    commands.entity(root_entity.world).with_child((
+        Music {
+            track,
+            elapsed: 0.0,
+            fading_out: false,
+        },
+        AudioChannel::Music,
+        AudioGain(0.0),
+        AudioPlayer::new(playlist.tracks[track].handle.clone()),
+        PlaybackSettings {
+            mode: PlaybackMode::Despawn,
+            ..Default::default()
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist(tracks: usize) -> MusicPlaylist {
+        MusicPlaylist {
+            tracks: (0..tracks)
+                .map(|i| Track {
+                    handle: Handle::default(),
+                    name: i.to_string(),
+                    duration: None,
+                    task: None,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn next_plays_every_track_per_round_without_repeats() {
+        let mut playlist = playlist(3);
+        let mut last = None;
+        for _ in 0..100 {
+            let mut round = (0..3).map(|_| playlist.next().unwrap()).collect::<Vec<_>>();
+            assert_ne!(Some(round[0]), last);
+            last = round.last().cloned();
+            round.sort();
+            assert_eq!(round, [0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn next_handles_small_playlists() {
+        assert_eq!(playlist(0).next(), None);
+        let mut playlist = playlist(1);
+        assert_eq!(playlist.next(), Some(0));
+        assert_eq!(playlist.next(), Some(0));
     }
 }
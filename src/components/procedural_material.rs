@@ -1,33 +1,46 @@
-use std::{any::TypeId, collections::BTreeMap, marker::PhantomData};
+use std::{any::TypeId, collections::BTreeSet, marker::PhantomData};
 
 use bevy::{
     core_pipeline::core_3d::graph::{Core3d, Node3d},
-    pbr::{ExtendedMaterial, MaterialExtension},
+    ecs::system::lifetimeless::SRes,
+    pbr::{ExtendedMaterial, MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline},
     prelude::*,
     render::{
+        mesh::MeshVertexBufferLayoutRef,
         render_graph::{Node, NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel},
         render_resource::{
             binding_types::{storage_buffer_read_only, texture_storage_2d_array, uniform_buffer},
             encase::{self, internal::WriteInto},
             AsBindGroup, AsBindGroupError, BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry,
             BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferInitDescriptor,
-            BufferUsages, CachedRenderPipelineId, ColorTargetState, ColorWrites, Extent3d,
+            BufferUsages, CachedComputePipelineId, CachedRenderPipelineId, ColorTargetState,
+            ColorWrites, ComputePassDescriptor, ComputePipelineDescriptor, Extent3d, FilterMode,
             FragmentState, IntoBinding, MultisampleState, Operations, OwnedBindingResource,
             PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
             RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderRef,
-            ShaderSize, ShaderStages, ShaderType, StorageTextureAccess, Texture, TextureDescriptor,
+            ShaderSize, ShaderStages, ShaderType, SpecializedMeshPipelineError,
+            SpecializedRenderPipelines, StorageTextureAccess, Texture, TextureDescriptor,
             TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
             TextureViewDescriptor, TextureViewDimension, UniformBuffer, UnpreparedBindGroup,
             VertexState,
         },
         renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::FallbackImage,
         MainWorld, RenderApp,
     },
+    utils::HashSet,
 };
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{EnumCount, EnumIter};
 
-use super::material_modifier::{MaterialModifierPlugin, OriginalMaterial};
+use super::{
+    material_modifier::{MaterialModifierPlugin, OriginalMaterial},
+    mipmaps::{
+        mip_level_count, render_mips, MipmapGeneratorPipeline, MipmapGeneratorPipelineKey,
+        MipmapGeneratorPlugin,
+    },
+    update_material_textures::UpdateMaterialTexturesPlugin,
+};
 
 pub struct ProceduralMaterialPlugin<Settings: ProceduralMaterial> {
     _pd: PhantomData<Settings>,
@@ -58,6 +71,11 @@ impl<Settings: ProceduralMaterial> Plugin for ProceduralMaterialPlugin<Settings>
             app,
             MaterialModifierPlugin::<ExtendedProceduralMaterial, StandardMaterial>::default(),
         );
+        try_add_plugin(app, MipmapGeneratorPlugin);
+        try_add_plugin(
+            app,
+            UpdateMaterialTexturesPlugin::<ExtendedProceduralMaterial>::default(),
+        );
 
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
@@ -94,7 +112,9 @@ impl<Settings: ProceduralMaterial> Plugin for ProceduralMaterialPlugin<Settings>
 #[derive(Component, PartialEq, Eq, PartialOrd, Ord)]
 struct EntityIndex(u32);
 
-#[derive(EnumIter, EnumCount, Clone, Copy)]
+// The generation shader writes a layer at the binding of its discriminant,
+// procedural.wgsl only samples the layers a material declares
+#[derive(EnumIter, EnumCount, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum TextureLayer {
     Diffuse,
     Emissive,
@@ -104,7 +124,7 @@ pub enum TextureLayer {
 }
 
 impl TextureLayer {
-    fn texture_format(&self) -> TextureFormat {
+    pub fn texture_format(&self) -> TextureFormat {
         match self {
             TextureLayer::Diffuse => TextureFormat::Rgba8Unorm,
             TextureLayer::Emissive => TextureFormat::Rgba16Float,
@@ -113,143 +133,443 @@ impl TextureLayer {
             TextureLayer::Normal => TextureFormat::Rgba8Unorm,
         }
     }
+
+    fn shader_def(&self) -> &'static str {
+        match self {
+            TextureLayer::Diffuse => "PROCEDURAL_DIFFUSE",
+            TextureLayer::Emissive => "PROCEDURAL_EMISSIVE",
+            TextureLayer::Metallic => "PROCEDURAL_METALLIC",
+            TextureLayer::Roughness => "PROCEDURAL_ROUGHNESS",
+            TextureLayer::Normal => "PROCEDURAL_NORMAL",
+        }
+    }
+}
+
+// `format` has to match the storage texture the generation shader declares
+#[derive(Clone, Copy, Debug)]
+pub struct ProceduralLayer {
+    pub layer: TextureLayer,
+    pub format: TextureFormat,
+    pub size: (u32, u32),
+}
+
+impl ProceduralLayer {
+    pub fn new(layer: TextureLayer, size: (u32, u32)) -> Self {
+        Self {
+            layer,
+            format: layer.texture_format(),
+            size,
+        }
+    }
+}
+
+// Fragment and compute invocations cover the largest layer, shaders scale the
+// coordinates of smaller layers with textureDimensions (`layer_coord`)
+fn grid_size(layers: &[ProceduralLayer]) -> (u32, u32) {
+    layers.iter().fold((1, 1), |(width, height), layer| {
+        (width.max(layer.size.0), height.max(layer.size.1))
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ProceduralPipeline {
+    // `vertex` and `fragment` entry points, one instance per dirty layer
+    Fragment,
+    // `compute` entry point with 8x8x1 workgroups, z indexes the dirty layer list
+    Compute,
 }
 
-pub trait ProceduralMaterial: Component + ShaderType + ShaderSize + WriteInto + Clone {
+pub const PROCEDURAL_SHADER: &str = "procedural.wgsl";
+const WORKGROUP_SIZE: u32 = 8;
+
+pub trait ProceduralMaterial:
+    Component + ShaderType + ShaderSize + WriteInto + Clone + PartialEq
+{
     fn shader() -> &'static str;
-    fn size() -> (u32, u32);
+
+    // Layers the shader writes, each at most once. Layers left out keep the
+    // StandardMaterial value
+    fn layers() -> Vec<ProceduralLayer>;
+
+    // Tangents are only needed to apply a generated normal map
+    fn tangents() -> bool {
+        Self::layers()
+            .iter()
+            .any(|layer| layer.layer == TextureLayer::Normal)
+    }
+
+    fn pipeline() -> ProceduralPipeline {
+        ProceduralPipeline::Fragment
+    }
+
+    // Generations per second of a layer whose settings keep changing, None
+    // regenerates on every change
+    fn update_rate() -> Option<f32> {
+        None
+    }
 }
 
-fn extract<Settings: ProceduralMaterial>(
-    mut commands: Commands,
-    mut main_world: ResMut<MainWorld>,
-    render_device: Res<RenderDevice>,
-    render_queue: Res<RenderQueue>,
-    buffer: Option<Res<ProceduralMaterialBufferRes<Settings>>>,
-    mut entity_index: Local<u32>,
-    mut count: Local<usize>,
-) {
-    let added = main_world
-        .query_filtered::<(Entity, &Mesh3d), (With<Settings>, Without<EntityIndex>)>()
-        .iter(&main_world)
-        .map(|(e, m)| (e, m.clone_weak()))
-        .collect::<Vec<_>>();
+// A pooled layer and the settings it was last generated with
+struct Layer<Settings> {
+    entity: Entity,
+    material: Handle<ExtendedProceduralMaterial>,
+    generated: Option<(Settings, f32)>,
+}
 
-    for (entity, _) in added.iter() {
-        main_world
-            .entity_mut(*entity)
-            .insert(EntityIndex(*entity_index));
-        *entity_index += 1;
+impl<Settings: PartialEq + Clone> Layer<Settings> {
+    fn new(entity: Entity, material: Handle<ExtendedProceduralMaterial>) -> Self {
+        Self {
+            entity,
+            material,
+            generated: None,
+        }
     }
 
-    let mut meshes = main_world.resource_mut::<Assets<Mesh>>();
-    for (_, mesh) in &added {
-        meshes.get_mut(mesh).unwrap().generate_tangents().unwrap();
+    // Whether the layer has to be generated again, in which case `settings`
+    // are recorded as generated at `now`
+    fn regenerate(&mut self, settings: &Settings, now: f32, interval: f32) -> bool {
+        if let Some((generated, at)) = &self.generated {
+            if generated == settings || now - at < interval {
+                return false;
+            }
+        }
+        self.generated = Some((settings.clone(), now));
+        true
     }
+}
 
-    let sorted = main_world
-        .query_filtered::<(Entity, &EntityIndex, &Settings), With<Mesh3d>>()
-        .iter(&main_world)
-        .map(|(e, i, s)| (i, (e, s.clone())))
-        .collect::<BTreeMap<_, _>>()
-        .into_values()
-        .collect::<Vec<_>>();
-    let data = sorted.iter().map(|(_, s)| s.clone()).collect::<Vec<_>>();
-
-    if added.is_empty() && *count == sorted.len() {
-        if let Some(buffer) = buffer {
-            let mut wrapper = encase::StorageBuffer::<Vec<u8>>::new(Vec::with_capacity(
-                data.size().get() as usize,
-            ));
-            wrapper.write(&data).unwrap();
-            render_queue.write_buffer(&buffer.buffer, 0, &wrapper.into_inner());
+// Texture array layers handed out to entities. A layer keeps its index for the
+// lifetime of the entity, freed layers are reused before the arrays grow
+struct LayerPool<T> {
+    slots: Vec<Option<T>>,
+    free: BTreeSet<u32>,
+}
+
+impl<T> Default for LayerPool<T> {
+    fn default() -> Self {
+        Self {
+            slots: vec![],
+            free: BTreeSet::new(),
         }
-    } else {
-        *count = sorted.len();
+    }
+}
 
-        commands.insert_resource(ProceduralMaterialBufferRes::<Settings> {
-            buffer: render_device.create_buffer(&BufferDescriptor {
-                label: None,
-                size: data.size().get(),
-                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            }),
-            _pd: PhantomData::default(),
-        });
+impl<T> LayerPool<T> {
+    fn allocate(&mut self, value: T) -> u32 {
+        match self.free.pop_first() {
+            Some(index) => {
+                self.slots[index as usize] = Some(value);
+                index
+            }
+            None => {
+                self.slots.push(Some(value));
+                self.slots.len() as u32 - 1
+            }
+        }
+    }
+
+    fn release(&mut self, index: u32) -> Option<T> {
+        let value = self.slots.get_mut(index as usize)?.take();
+        if value.is_some() {
+            self.free.insert(index);
+        }
+        value
+    }
+
+    fn get_mut(&mut self, index: u32) -> Option<&mut T> {
+        self.slots.get_mut(index as usize)?.as_mut()
+    }
+
+    // Layers the texture arrays need to hold every allocated index
+    fn len(&self) -> u32 {
+        self.slots.len() as u32
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (u32, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| Some((i as u32, slot.as_ref()?)))
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (u32, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, slot)| Some((i as u32, slot.as_mut()?)))
+    }
+}
 
-        let proc_mat_texture = |layer: TextureLayer| {
-            let (width, height) = <Settings as ProceduralMaterial>::size();
-            let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+const MIN_LAYERS: u32 = 4;
+
+fn create_textures<Settings: ProceduralMaterial>(
+    render_device: &RenderDevice,
+    layers: u32,
+    mip_pipelines: &mut SpecializedRenderPipelines<MipmapGeneratorPipeline>,
+    mip_gen_pipeline: &MipmapGeneratorPipeline,
+    pipeline_cache: &PipelineCache,
+) -> ProceduralMaterialTextures {
+    let sampler = render_device.create_sampler(&SamplerDescriptor {
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        mipmap_filter: FilterMode::Linear,
+        ..Default::default()
+    });
+    let textures = Settings::layers()
+        .into_iter()
+        .map(|layer| {
+            let (width, height) = layer.size;
             let texture = render_device.create_texture(&TextureDescriptor {
                 label: None,
                 size: Extent3d {
                     width,
                     height,
-                    depth_or_array_layers: sorted.len().max(2) as u32,
+                    depth_or_array_layers: layers,
                 },
-                mip_level_count: 1,
+                mip_level_count: mip_level_count(width, height),
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: layer.texture_format(),
-                usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+                format: layer.format,
+                usage: TextureUsages::STORAGE_BINDING
+                    | TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::RENDER_ATTACHMENT,
                 view_formats: &vec![],
             });
-            let view = texture.create_view(&TextureViewDescriptor::default());
-            ProceduralMaterialTexture {
-                sampler,
-                texture,
-                view,
+            let view = texture.create_view(&TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::D2Array),
+                ..Default::default()
+            });
+            // The procedural shader only writes the full resolution level
+            let storage_view = texture.create_view(&TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::D2Array),
+                mip_level_count: Some(1),
+                ..Default::default()
+            });
+            let mips = mip_pipelines.specialize(
+                pipeline_cache,
+                mip_gen_pipeline,
+                MipmapGeneratorPipelineKey {
+                    texture_format: layer.format,
+                },
+            );
+            (
+                layer.layer,
+                ProceduralMaterialTexture {
+                    sampler: sampler.clone(),
+                    texture,
+                    view,
+                    storage_view,
+                    mips,
+                },
+            )
+        })
+        .collect();
+
+    ProceduralMaterialTextures { layers, textures }
+}
+
+fn extract<Settings: ProceduralMaterial>(
+    mut commands: Commands,
+    mut main_world: ResMut<MainWorld>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    buffer: Option<Res<ProceduralMaterialBufferRes<Settings>>>,
+    textures: Option<Res<ProceduralMaterialTexturesRes<Settings>>>,
+    mut mip_pipelines: ResMut<SpecializedRenderPipelines<MipmapGeneratorPipeline>>,
+    mip_gen_pipeline: Res<MipmapGeneratorPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    proc_mat_pipeline: Res<ProceduralMaterialPipeline<Settings>>,
+    mut pool: Local<LayerPool<Layer<Settings>>>,
+) {
+    let live = main_world
+        .query_filtered::<Entity, (With<Settings>, With<EntityIndex>, With<Mesh3d>)>()
+        .iter(&main_world)
+        .collect::<HashSet<_>>();
+    let released = pool
+        .iter()
+        .filter(|(_, layer)| !live.contains(&layer.entity))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    for index in released {
+        if let Some(layer) = pool.release(index) {
+            if let Ok(mut entity) = main_world.get_entity_mut(layer.entity) {
+                entity.remove::<EntityIndex>();
             }
-        };
+        }
+    }
 
-        let textures = ProceduralMaterialTextures {
-            color: proc_mat_texture(TextureLayer::Diffuse),
-            emissive: proc_mat_texture(TextureLayer::Emissive),
-            metallic: proc_mat_texture(TextureLayer::Metallic),
-            roughness: proc_mat_texture(TextureLayer::Roughness),
-            normal: proc_mat_texture(TextureLayer::Normal),
-        };
+    let added = main_world
+        .query_filtered::<(Entity, &Mesh3d), (With<Settings>, Without<EntityIndex>)>()
+        .iter(&main_world)
+        .map(|(e, m)| (e, m.clone_weak()))
+        .collect::<Vec<_>>();
 
-        commands.insert_resource(ProceduralMaterialTexturesRes::<Settings> {
-            textures: textures.clone(),
-            _pd: PhantomData::default(),
-        });
+    if Settings::tangents() {
+        let mut meshes = main_world.resource_mut::<Assets<Mesh>>();
+        for (_, mesh) in &added {
+            meshes.get_mut(mesh).unwrap().generate_tangents().unwrap();
+        }
+    }
 
-        let mut materials = main_world.resource_mut::<Assets<ExtendedProceduralMaterial>>();
+    let mut materials = main_world.resource_mut::<Assets<ExtendedProceduralMaterial>>();
+    let added = added
+        .into_iter()
+        .map(|(entity, _)| {
+            let material = materials.reserve_handle();
+            (
+                entity,
+                pool.allocate(Layer::new(entity, material.clone())),
+                material,
+            )
+        })
+        .collect::<Vec<_>>();
 
-        let materials = sorted
-            .iter()
-            .enumerate()
-            .map(|(i, _)| {
-                materials.add(ExtendedMaterial {
-                    base: StandardMaterial {
-                        base_color: Color::WHITE,
-                        emissive: LinearRgba::WHITE,
-                        alpha_mode: AlphaMode::Blend,
-                        ..Default::default()
-                    },
-                    extension: ProceduralMaterialExtension {
-                        textures: textures.clone(),
-                        index: i as u32,
-                        add_emission: LinearRgba::NONE,
-                    },
-                })
-            })
-            .collect::<Vec<_>>();
-
-        for ((entity, _), material) in sorted.iter().zip(materials) {
-            let mut entity = main_world.entity_mut(*entity);
-            if entity.contains::<OriginalMaterial<ExtendedProceduralMaterial>>() {
-                entity
-                    .remove::<OriginalMaterial<ExtendedProceduralMaterial>>()
-                    .insert(OriginalMaterial(material));
-            } else {
-                entity
-                    .remove::<MeshMaterial3d<ExtendedProceduralMaterial>>()
-                    .insert(MeshMaterial3d(material));
+    if pool.len() == 0 && textures.is_none() {
+        return;
+    }
+
+    // Growing the arrays is the only time existing materials are touched
+    let layers = textures.as_ref().map_or(0, |t| t.textures.layers);
+    let (textures, buffer, dirty_buffer) = match (textures, buffer) {
+        (Some(textures), Some(buffer)) if pool.len() <= layers => (
+            textures.textures.clone(),
+            buffer.buffer.clone(),
+            buffer.dirty.clone(),
+        ),
+        _ => {
+            let layers = pool.len().next_power_of_two().max(MIN_LAYERS);
+            let textures = create_textures::<Settings>(
+                &render_device,
+                layers,
+                &mut mip_pipelines,
+                &mip_gen_pipeline,
+                &pipeline_cache,
+            );
+            // The new arrays start out empty
+            for (_, layer) in pool.iter_mut() {
+                layer.generated = None;
+                if let Some(material) = materials.get_mut(&layer.material) {
+                    material.extension.textures = textures.clone();
+                }
             }
+            let create_buffer = |size: u64| {
+                render_device.create_buffer(&BufferDescriptor {
+                    label: None,
+                    size: size * layers as u64,
+                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            };
+            let buffer = create_buffer(Settings::SHADER_SIZE.get());
+            let dirty_buffer = create_buffer(u32::SHADER_SIZE.get());
+            commands.insert_resource(ProceduralMaterialTexturesRes::<Settings> {
+                textures: textures.clone(),
+                _pd: PhantomData::default(),
+            });
+            commands.insert_resource(ProceduralMaterialBufferRes::<Settings> {
+                buffer: buffer.clone(),
+                dirty: dirty_buffer.clone(),
+                _pd: PhantomData::default(),
+            });
+            (textures, buffer, dirty_buffer)
         }
+    };
+
+    for (_, index, material) in &added {
+        materials.insert(
+            material,
+            ExtendedMaterial {
+                base: StandardMaterial {
+                    base_color: Color::WHITE,
+                    emissive: if textures.get(TextureLayer::Emissive).is_some() {
+                        LinearRgba::WHITE
+                    } else {
+                        LinearRgba::BLACK
+                    },
+                    alpha_mode: AlphaMode::Blend,
+                    ..Default::default()
+                },
+                extension: ProceduralMaterialExtension {
+                    textures: textures.clone(),
+                    index: *index,
+                    add_emission: LinearRgba::NONE,
+                },
+            },
+        );
     }
+    for (entity, index, material) in added {
+        let mut entity = main_world.entity_mut(entity);
+        entity.insert(EntityIndex(index));
+        if entity.contains::<OriginalMaterial<ExtendedProceduralMaterial>>() {
+            entity
+                .remove::<OriginalMaterial<ExtendedProceduralMaterial>>()
+                .insert(OriginalMaterial(material));
+        } else {
+            entity
+                .remove::<MeshMaterial3d<ExtendedProceduralMaterial>>()
+                .insert(MeshMaterial3d(material));
+        }
+    }
+
+    // The node skips passes whose pipelines aren't compiled yet, so layers are
+    // only taken as generated once all of them are ready
+    let ready = proc_mat_pipeline.pipeline_id.is_ready(&pipeline_cache)
+        && textures
+            .textures
+            .iter()
+            .all(|(_, texture)| pipeline_cache.get_render_pipeline(texture.mips).is_some());
+    if !ready {
+        commands.insert_resource(ProceduralMaterialDirtyRes::<Settings> {
+            layers: vec![],
+            _pd: PhantomData::default(),
+        });
+        return;
+    }
+
+    // Only layers whose settings changed are generated again
+    let now = main_world.resource::<Time>().elapsed_secs();
+    let interval = Settings::update_rate().map_or(0.0, |rate| 1.0 / rate);
+    let mut regenerated = main_world
+        .query_filtered::<(&EntityIndex, &Settings), With<Mesh3d>>()
+        .iter(&main_world)
+        .filter_map(|(EntityIndex(i), settings)| {
+            pool.get_mut(*i)?
+                .regenerate(settings, now, interval)
+                .then(|| (*i, settings.clone()))
+        })
+        .collect::<Vec<_>>();
+    regenerated.sort_by_key(|(i, _)| *i);
+    let dirty = regenerated.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+    commands.insert_resource(ProceduralMaterialDirtyRes::<Settings> {
+        layers: dirty.clone(),
+        _pd: PhantomData::default(),
+    });
+    if dirty.is_empty() {
+        return;
+    }
+
+    // The shader only reads the settings of dirty layers, so only those slots are written
+    for (i, settings) in &regenerated {
+        write_storage(
+            &render_queue,
+            &buffer,
+            *i as u64 * Settings::SHADER_SIZE.get(),
+            settings,
+        );
+    }
+    write_storage(&render_queue, &dirty_buffer, 0, &dirty);
+}
+
+fn write_storage<T: ShaderType + WriteInto>(
+    render_queue: &RenderQueue,
+    buffer: &Buffer,
+    offset: u64,
+    data: &T,
+) {
+    let mut wrapper =
+        encase::StorageBuffer::<Vec<u8>>::new(Vec::with_capacity(data.size().get() as usize));
+    wrapper.write(data).unwrap();
+    render_queue.write_buffer(buffer, offset, &wrapper.into_inner());
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
@@ -279,10 +599,12 @@ impl<Settings: ProceduralMaterial> Node for ProceduralMaterialNode<Settings> {
         let proc_mat_pipeline = world.resource::<ProceduralMaterialPipeline<Settings>>();
         let pipeline_cache = world.resource::<PipelineCache>();
 
-        let Some(main_pipeline) = pipeline_cache.get_render_pipeline(proc_mat_pipeline.pipeline_id)
-        else {
+        let Some(dirty) = world.get_resource::<ProceduralMaterialDirtyRes<Settings>>() else {
             return Ok(());
         };
+        if dirty.layers.is_empty() {
+            return Ok(());
+        }
 
         let Some(buffer) = world.get_resource::<ProceduralMaterialBufferRes<Settings>>() else {
             return Ok(());
@@ -297,11 +619,12 @@ impl<Settings: ProceduralMaterial> Node for ProceduralMaterialNode<Settings> {
         let main_bind_group = render_context.render_device().create_bind_group(
             "proc_mat_bind_group",
             &proc_mat_pipeline.layout,
-            &TextureLayer::iter()
-                .enumerate()
-                .map(|(i, l)| BindGroupEntry {
-                    binding: i as u32,
-                    resource: textures.get(l).view.into_binding(),
+            &textures
+                .textures
+                .iter()
+                .map(|(layer, texture)| BindGroupEntry {
+                    binding: *layer as u32,
+                    resource: texture.storage_view.into_binding(),
                 })
                 .chain([
                     BindGroupEntry {
@@ -312,41 +635,99 @@ impl<Settings: ProceduralMaterial> Node for ProceduralMaterialNode<Settings> {
                         binding: 100,
                         resource: buffer.buffer.as_entire_binding(),
                     },
+                    BindGroupEntry {
+                        binding: 101,
+                        resource: buffer.dirty.as_entire_binding(),
+                    },
                 ])
                 .collect::<Vec<_>>(),
         );
 
-        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
-            label: Some("proc_mat_pass"),
-            color_attachments: &[Some(RenderPassColorAttachment {
-                view: &proc_mat_pipeline.dummy_texture_view,
-                resolve_target: None,
-                ops: Operations::default(),
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
-        });
+        match proc_mat_pipeline.pipeline_id {
+            ProceduralPipelineId::Fragment(pipeline_id) => {
+                let Some(main_pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+                    return Ok(());
+                };
+
+                let mut render_pass =
+                    render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                        label: Some("proc_mat_pass"),
+                        color_attachments: &[Some(RenderPassColorAttachment {
+                            view: &proc_mat_pipeline.dummy_texture_view,
+                            resolve_target: None,
+                            ops: Operations::default(),
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+
+                render_pass.set_render_pipeline(main_pipeline);
+                render_pass.set_bind_group(0, &main_bind_group, &[]);
+                for layer in &dirty.layers {
+                    render_pass.draw(0..3, *layer..*layer + 1);
+                }
+            }
+            ProceduralPipelineId::Compute(pipeline_id) => {
+                let Some(main_pipeline) = pipeline_cache.get_compute_pipeline(pipeline_id) else {
+                    return Ok(());
+                };
+
+                let (width, height) = grid_size(&Settings::layers());
+                let mut compute_pass =
+                    render_context
+                        .command_encoder()
+                        .begin_compute_pass(&ComputePassDescriptor {
+                            label: Some("proc_mat_compute_pass"),
+                            timestamp_writes: None,
+                        });
+
+                compute_pass.set_pipeline(main_pipeline);
+                compute_pass.set_bind_group(0, &main_bind_group, &[]);
+                compute_pass.dispatch_workgroups(
+                    width.div_ceil(WORKGROUP_SIZE),
+                    height.div_ceil(WORKGROUP_SIZE),
+                    dirty.layers.len() as u32,
+                );
+            }
+        }
 
-        render_pass.set_render_pipeline(main_pipeline);
-        render_pass.set_bind_group(0, &main_bind_group, &[]);
-        render_pass.draw(
-            0..3,
-            0..textures
-                .get(TextureLayer::Diffuse)
-                .texture
-                .size()
-                .depth_or_array_layers,
-        );
+        let mip_gen_pipeline = world.resource::<MipmapGeneratorPipeline>();
+        for (_, texture) in &textures.textures {
+            if let Some(pipeline) = pipeline_cache.get_render_pipeline(texture.mips) {
+                render_mips(
+                    render_context,
+                    mip_gen_pipeline,
+                    pipeline,
+                    &texture.texture,
+                    dirty.layers.iter().copied(),
+                );
+            }
+        }
 
         Ok(())
     }
 }
 
+#[derive(Clone, Copy)]
+enum ProceduralPipelineId {
+    Fragment(CachedRenderPipelineId),
+    Compute(CachedComputePipelineId),
+}
+
+impl ProceduralPipelineId {
+    fn is_ready(&self, pipeline_cache: &PipelineCache) -> bool {
+        match *self {
+            Self::Fragment(id) => pipeline_cache.get_render_pipeline(id).is_some(),
+            Self::Compute(id) => pipeline_cache.get_compute_pipeline(id).is_some(),
+        }
+    }
+}
+
 #[derive(Resource)]
 struct ProceduralMaterialPipeline<Settings> {
     layout: BindGroupLayout,
-    pipeline_id: CachedRenderPipelineId,
+    pipeline_id: ProceduralPipelineId,
     dummy_texture_view: TextureView,
     globals: UniformBuffer<ProceduralMaterialGlobals>,
     _pd: PhantomData<Settings>,
@@ -357,24 +738,26 @@ impl<Settings: ProceduralMaterial> FromWorld for ProceduralMaterialPipeline<Sett
         let render_device = world.resource::<RenderDevice>();
         let render_queue = world.resource::<RenderQueue>();
 
+        let stages = ShaderStages::FRAGMENT | ShaderStages::COMPUTE;
         let layout = render_device.create_bind_group_layout(
             "proc_mat_bind_group_layout",
-            TextureLayer::iter()
-                .enumerate()
-                .map(|(i, l)| {
-                    texture_storage_2d_array(l.texture_format(), StorageTextureAccess::WriteOnly)
-                        .build(i as u32, ShaderStages::FRAGMENT)
+            Settings::layers()
+                .into_iter()
+                .map(|layer| {
+                    texture_storage_2d_array(layer.format, StorageTextureAccess::WriteOnly)
+                        .build(layer.layer as u32, stages)
                 })
                 .chain([
                     uniform_buffer::<ProceduralMaterialGlobals>(false)
-                        .build(TextureLayer::COUNT as u32, ShaderStages::FRAGMENT),
-                    storage_buffer_read_only::<Settings>(false).build(100, ShaderStages::FRAGMENT),
+                        .build(TextureLayer::COUNT as u32, stages),
+                    storage_buffer_read_only::<Settings>(false).build(100, stages),
+                    storage_buffer_read_only::<u32>(false).build(101, stages),
                 ])
                 .collect::<Vec<_>>()
                 .as_slice(),
         );
 
-        let (width, height) = <Settings as ProceduralMaterial>::size();
+        let (width, height) = grid_size(&Settings::layers());
 
         let dummy_texture = render_device.create_texture(&{
             let mut desc = Image::default().texture_descriptor;
@@ -397,10 +780,10 @@ impl<Settings: ProceduralMaterial> FromWorld for ProceduralMaterialPipeline<Sett
         globals.write_buffer(render_device, render_queue);
 
         let shader = world.load_asset(Settings::shader());
-        let pipeline_id =
-            world
-                .resource_mut::<PipelineCache>()
-                .queue_render_pipeline(RenderPipelineDescriptor {
+        let pipeline_cache = world.resource_mut::<PipelineCache>();
+        let pipeline_id = match Settings::pipeline() {
+            ProceduralPipeline::Fragment => ProceduralPipelineId::Fragment(
+                pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
                     label: Some("proc_mat_pipeline".into()),
                     layout: vec![layout.clone()],
                     vertex: VertexState {
@@ -424,7 +807,20 @@ impl<Settings: ProceduralMaterial> FromWorld for ProceduralMaterialPipeline<Sett
                     multisample: MultisampleState::default(),
                     push_constant_ranges: vec![],
                     zero_initialize_workgroup_memory: false,
-                });
+                }),
+            ),
+            ProceduralPipeline::Compute => ProceduralPipelineId::Compute(
+                pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("proc_mat_compute_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    push_constant_ranges: vec![],
+                    shader,
+                    shader_defs: vec![],
+                    entry_point: "compute".into(),
+                    zero_initialize_workgroup_memory: false,
+                }),
+            ),
+        };
 
         Self {
             layout,
@@ -445,42 +841,36 @@ struct ProceduralMaterialGlobals {
 struct ProceduralMaterialTexture {
     texture: Texture,
     view: TextureView,
+    storage_view: TextureView,
     sampler: Sampler,
-}
-
-impl ProceduralMaterialTexture {
-    fn to_binding(&self, view: u32, sampler: u32) -> [(u32, OwnedBindingResource); 2] {
-        [
-            (view, OwnedBindingResource::TextureView(self.view.clone())),
-            (sampler, OwnedBindingResource::Sampler(self.sampler.clone())),
-        ]
-    }
+    mips: CachedRenderPipelineId,
 }
 
 #[derive(Clone, Debug)]
 struct ProceduralMaterialTextures {
-    color: ProceduralMaterialTexture,
-    emissive: ProceduralMaterialTexture,
-    metallic: ProceduralMaterialTexture,
-    roughness: ProceduralMaterialTexture,
-    normal: ProceduralMaterialTexture,
+    layers: u32,
+    textures: Vec<(TextureLayer, ProceduralMaterialTexture)>,
 }
 
 impl ProceduralMaterialTextures {
-    fn get(&self, layer: TextureLayer) -> &ProceduralMaterialTexture {
-        match layer {
-            TextureLayer::Diffuse => &self.color,
-            TextureLayer::Emissive => &self.emissive,
-            TextureLayer::Metallic => &self.metallic,
-            TextureLayer::Roughness => &self.roughness,
-            TextureLayer::Normal => &self.normal,
-        }
+    fn get(&self, layer: TextureLayer) -> Option<&ProceduralMaterialTexture> {
+        self.textures
+            .iter()
+            .find_map(|(l, texture)| (*l == layer).then_some(texture))
     }
 }
 
 #[derive(Resource)]
 struct ProceduralMaterialBufferRes<Settings: ProceduralMaterial> {
     buffer: Buffer,
+    dirty: Buffer,
+    _pd: PhantomData<Settings>,
+}
+
+// Layers to generate this frame, in ascending order
+#[derive(Resource)]
+struct ProceduralMaterialDirtyRes<Settings: ProceduralMaterial> {
+    layers: Vec<u32>,
     _pd: PhantomData<Settings>,
 }
 
@@ -497,6 +887,12 @@ pub struct ProceduralMaterialExtension {
     pub add_emission: LinearRgba,
 }
 
+// Bit per declared TextureLayer, picks the shader defs of procedural.wgsl
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ProceduralMaterialKey {
+    layers: u32,
+}
+
 pub type ExtendedProceduralMaterial =
     ExtendedMaterial<StandardMaterial, ProceduralMaterialExtension>;
 
@@ -506,8 +902,8 @@ impl ProceduralMaterialExtension {
 }
 
 impl AsBindGroup for ProceduralMaterialExtension {
-    type Data = ();
-    type Param = ();
+    type Data = ProceduralMaterialKey;
+    type Param = SRes<FallbackImage>;
 
     fn label() -> Option<&'static str> {
         Some("ProceduralMaterialExtension")
@@ -525,7 +921,7 @@ impl AsBindGroup for ProceduralMaterialExtension {
                         binding: Self::BINDING_TEXTURES + i as u32 * 2,
                         visibility: ShaderStages::all(),
                         ty: BindingType::Texture {
-                            sample_type: TextureSampleType::Float { filterable: false },
+                            sample_type: TextureSampleType::Float { filterable: true },
                             view_dimension: TextureViewDimension::D2Array,
                             multisampled: false,
                         },
@@ -534,7 +930,7 @@ impl AsBindGroup for ProceduralMaterialExtension {
                     BindGroupLayoutEntry {
                         binding: Self::BINDING_TEXTURES + i as u32 * 2 + 1,
                         visibility: ShaderStages::all(),
-                        ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
                         count: None,
                     },
                 ]
@@ -569,16 +965,30 @@ impl AsBindGroup for ProceduralMaterialExtension {
         &self,
         _: &BindGroupLayout,
         render_device: &RenderDevice,
-        _: &mut bevy::ecs::system::SystemParamItem<'_, '_, Self::Param>,
+        fallback_image: &mut bevy::ecs::system::SystemParamItem<'_, '_, Self::Param>,
     ) -> Result<UnpreparedBindGroup<Self::Data>, AsBindGroupError> {
         Ok(UnpreparedBindGroup {
             bindings: TextureLayer::iter()
                 .enumerate()
                 .map(|(i, l)| {
-                    self.textures.get(l).to_binding(
-                        Self::BINDING_TEXTURES + i as u32 * 2,
-                        Self::BINDING_TEXTURES + i as u32 * 2 + 1,
-                    )
+                    // Undeclared layers still need a binding, the shader never samples it
+                    let (view, sampler) = match self.textures.get(l) {
+                        Some(texture) => (texture.view.clone(), texture.sampler.clone()),
+                        None => (
+                            fallback_image.d2_array.texture_view.clone(),
+                            fallback_image.d2_array.sampler.clone(),
+                        ),
+                    };
+                    [
+                        (
+                            Self::BINDING_TEXTURES + i as u32 * 2,
+                            OwnedBindingResource::TextureView(view),
+                        ),
+                        (
+                            Self::BINDING_TEXTURES + i as u32 * 2 + 1,
+                            OwnedBindingResource::Sampler(sampler),
+                        ),
+                    ]
                 })
                 .flatten()
                 .chain(
@@ -611,7 +1021,13 @@ impl AsBindGroup for ProceduralMaterialExtension {
                     .map(|(i, r)| (Self::BINDING_UNIFORMS + i as u32, r)),
                 )
                 .collect(),
-            data: (),
+            data: ProceduralMaterialKey {
+                layers: self
+                    .textures
+                    .textures
+                    .iter()
+                    .fold(0, |layers, (layer, _)| layers | 1 << *layer as u32),
+            },
         })
     }
 }
@@ -628,6 +1044,74 @@ impl TypePath for ProceduralMaterialExtension {
 
 impl MaterialExtension for ProceduralMaterialExtension {
     fn fragment_shader() -> ShaderRef {
-        "procedural.wgsl".into()
+        PROCEDURAL_SHADER.into()
+    }
+
+    fn specialize(
+        _: &MaterialExtensionPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        _: &MeshVertexBufferLayoutRef,
+        key: MaterialExtensionKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            for layer in TextureLayer::iter() {
+                if key.bind_group_data.layers & 1 << layer as u32 != 0 {
+                    fragment.shader_defs.push(layer.shader_def().into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_layers_are_reused_first() {
+        let mut pool = LayerPool::default();
+        assert_eq!(pool.allocate('a'), 0);
+        assert_eq!(pool.allocate('b'), 1);
+        assert_eq!(pool.allocate('c'), 2);
+
+        assert_eq!(pool.release(1), Some('b'));
+        assert_eq!(pool.release(1), None);
+        assert_eq!(pool.release(0), Some('a'));
+        assert_eq!(pool.len(), 3);
+
+        assert_eq!(pool.allocate('d'), 0);
+        assert_eq!(pool.allocate('e'), 1);
+        assert_eq!(pool.allocate('f'), 3);
+        assert_eq!(
+            pool.iter().collect::<Vec<_>>(),
+            vec![(0, &'d'), (1, &'e'), (2, &'c'), (3, &'f')]
+        );
+    }
+
+    #[test]
+    fn grid_covers_every_layer() {
+        assert_eq!(grid_size(&[]), (1, 1));
+        assert_eq!(
+            grid_size(&[
+                ProceduralLayer::new(TextureLayer::Emissive, (64, 16)),
+                ProceduralLayer::new(TextureLayer::Normal, (32, 32)),
+            ]),
+            (64, 32)
+        );
+    }
+
+    #[test]
+    fn layers_regenerate_on_change_at_most_at_the_update_rate() {
+        let mut layer = Layer::new(Entity::PLACEHOLDER, Handle::default());
+        assert!(layer.regenerate(&1.0, 0.0, 0.5));
+        assert!(!layer.regenerate(&1.0, 1.0, 0.5));
+        assert!(layer.regenerate(&2.0, 1.0, 0.5));
+        assert!(!layer.regenerate(&3.0, 1.2, 0.5));
+        assert!(layer.regenerate(&3.0, 1.5, 0.5));
+        assert!(!layer.regenerate(&3.0, 9.0, 0.5));
+
+        layer.generated = None;
+        assert!(layer.regenerate(&3.0, 9.1, 0.5));
     }
 }
@@ -0,0 +1,217 @@
+use std::{collections::BTreeSet, f32::consts::TAU, sync::Arc, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source, Volume},
+    prelude::*,
+};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::scenes::main_menu::settings::GameSettings;
+
+const SAMPLE_RATE: u32 = 44100;
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<SfxSound>()
+            .init_resource::<PendingSfx>()
+            .add_systems(Startup, setup)
+            .add_systems(PostUpdate, play)
+            .add_observer(
+                |trigger: Trigger<PlaySfx>, mut pending: ResMut<PendingSfx>| {
+                    pending.0.insert(trigger.event().0);
+                },
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AudioChannel {
+    Music,
+    Effects,
+}
+
+// Multiplies the channel volume, used for fades
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AudioGain(pub f32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter)]
+pub enum Sfx {
+    Click,
+    HookLaunch,
+    HookCatch,
+    RockImpact,
+    Construct,
+    Destruct,
+    LowPower,
+}
+
+#[derive(Event)]
+pub struct PlaySfx(pub Sfx);
+
+#[derive(Asset, TypePath)]
+pub struct SfxSound {
+    samples: Arc<[f32]>,
+}
+
+pub struct SfxDecoder {
+    samples: Arc<[f32]>,
+    pos: usize,
+}
+
+impl Iterator for SfxDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.samples.get(self.pos).copied();
+        self.pos += 1;
+        sample
+    }
+}
+
+impl Source for SfxDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.len().saturating_sub(self.pos))
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(
+            self.samples.len() as f32 / SAMPLE_RATE as f32,
+        ))
+    }
+}
+
+impl Decodable for SfxSound {
+    type DecoderItem = f32;
+    type Decoder = SfxDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SfxDecoder {
+            samples: self.samples.clone(),
+            pos: 0,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SfxHandles(Vec<(Sfx, Handle<SfxSound>)>);
+
+// Sounds triggered several times in one frame are played once
+#[derive(Resource, Default)]
+struct PendingSfx(BTreeSet<Sfx>);
+
+impl Sfx {
+    fn synthesize(&self) -> Vec<f32> {
+        let mut noise = Noise(0x2545_f491);
+        match self {
+            Self::Click => render(0.04, |t| sine(1400.0, t) * decay(t, 60.0) * 0.4),
+            Self::HookLaunch => render(0.25, |t| {
+                let freq = 300.0 + 1200.0 * t;
+                sine(freq * 0.5, t) * decay(t, 10.0) * 0.35 + noise.next() * decay(t, 20.0) * 0.1
+            }),
+            Self::HookCatch => render(0.3, |t| {
+                (sine(620.0, t) + 0.6 * sine(1470.0, t)) * decay(t, 14.0) * 0.3
+                    + noise.next() * decay(t, 60.0) * 0.3
+            }),
+            Self::RockImpact => render(0.45, |t| {
+                let freq = 90.0 - 50.0 * t;
+                sine(freq, t) * decay(t, 8.0) * 0.6 + noise.next() * decay(t, 18.0) * 0.35
+            }),
+            Self::Construct => render(0.36, |t| {
+                let note = [523.25, 659.25, 783.99][((t / 0.12) as usize).min(2)];
+                sine(note, t) * decay(t % 0.12, 20.0) * 0.3
+            }),
+            Self::Destruct => render(0.5, |t| {
+                let freq = 400.0 - 340.0 * t * 2.0;
+                sine(freq * 0.5, t) * decay(t, 6.0) * 0.3 + noise.next() * decay(t, 7.0) * 0.3
+            }),
+            Self::LowPower => render(0.5, |t| {
+                let (freq, start) = if t < 0.25 {
+                    (880.0, 0.0)
+                } else {
+                    (660.0, 0.25)
+                };
+                if t - start < 0.18 {
+                    sine(freq, t) * 0.25
+                } else {
+                    0.0
+                }
+            }),
+        }
+    }
+}
+
+fn render(duration: f32, mut f: impl FnMut(f32) -> f32) -> Vec<f32> {
+    let len = (duration * SAMPLE_RATE as f32) as usize;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            // Short fade out so nothing clicks when the buffer ends
+            let fade = ((len - i) as f32 / 200.0).min(1.0);
+            f(t) * fade
+        })
+        .collect()
+}
+
+fn sine(freq: f32, t: f32) -> f32 {
+    (TAU * freq * t).sin()
+}
+
+fn decay(t: f32, rate: f32) -> f32 {
+    (-t * rate).exp()
+}
+
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+fn setup(mut commands: Commands, mut sounds: ResMut<Assets<SfxSound>>) {
+    commands.insert_resource(SfxHandles(
+        Sfx::iter()
+            .map(|sfx| {
+                let handle = sounds.add(SfxSound {
+                    samples: sfx.synthesize().into(),
+                });
+                (sfx, handle)
+            })
+            .collect(),
+    ));
+}
+
+fn play(
+    mut commands: Commands,
+    mut pending: ResMut<PendingSfx>,
+    handles: Res<SfxHandles>,
+    settings: Res<GameSettings>,
+) {
+    for sfx in std::mem::take(&mut pending.0) {
+        if settings.effects_volume <= 0.0 {
+            continue;
+        }
+        let Some((_, handle)) = handles.0.iter().find(|(s, _)| *s == sfx) else {
+            continue;
+        };
+        commands.spawn((
+            AudioChannel::Effects,
+            AudioPlayer(handle.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(settings.effects_volume)),
+        ));
+    }
+}
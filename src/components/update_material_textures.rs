@@ -1,15 +1,25 @@
 use std::marker::PhantomData;
 
 use bevy::{
+    pbr::{ExtendedMaterial, MaterialExtension},
     prelude::*,
     utils::{HashMap, HashSet},
 };
 
-#[derive(Default)]
+// Re-prepares materials when one of their images changes, otherwise they keep
+// binding the texture uploaded before a hot reload
 pub struct UpdateMaterialTexturesPlugin<Material> {
     _pd: PhantomData<Material>,
 }
 
+impl<Material> Default for UpdateMaterialTexturesPlugin<Material> {
+    fn default() -> Self {
+        Self {
+            _pd: PhantomData::default(),
+        }
+    }
+}
+
 pub trait MaterialTextures {
     fn textures(&self) -> Vec<&Option<Handle<Image>>>;
 }
@@ -33,6 +43,30 @@ impl MaterialTextures for StandardMaterial {
     }
 }
 
+// Extensions in this project have no image handles of their own
+impl<Extension: MaterialExtension> MaterialTextures
+    for ExtendedMaterial<StandardMaterial, Extension>
+{
+    fn textures(&self) -> Vec<&Option<Handle<Image>>> {
+        self.base.textures()
+    }
+}
+
+fn unlink<Material: Asset>(
+    material: &AssetId<Material>,
+    image_to_materials: &mut HashMap<AssetId<Image>, HashSet<AssetId<Material>>>,
+    material_to_images: &mut HashMap<AssetId<Material>, HashSet<AssetId<Image>>>,
+) {
+    for image in material_to_images.remove(material).unwrap_or_default() {
+        if let Some(image_materials) = image_to_materials.get_mut(&image) {
+            image_materials.remove(material);
+            if image_materials.is_empty() {
+                image_to_materials.remove(&image);
+            }
+        }
+    }
+}
+
 fn update<Material: Asset + MaterialTextures>(
     mut image_to_materials: Local<HashMap<AssetId<Image>, HashSet<AssetId<Material>>>>,
     mut material_to_images: Local<HashMap<AssetId<Material>, HashSet<AssetId<Image>>>>,
@@ -43,28 +77,26 @@ fn update<Material: Asset + MaterialTextures>(
     for event in material_events.read() {
         match event {
             AssetEvent::Added { id } | AssetEvent::Modified { id } => {
+                unlink(id, &mut image_to_materials, &mut material_to_images);
                 let Some(material) = materials.get(*id) else {
                     continue;
                 };
-                let material_images = material_to_images.entry(*id).or_default();
-                for image in material
+                let material_images = material
                     .textures()
                     .into_iter()
                     .filter_map(|img| img.as_ref())
-                {
-                    material_images.insert(image.id());
+                    .map(|img| img.id())
+                    .collect::<HashSet<_>>();
+                if material_images.is_empty() {
+                    continue;
                 }
                 for image in material_images.iter() {
-                    image_to_materials
-                        .entry(*image)
-                        .or_default()
-                        .insert(id.clone());
+                    image_to_materials.entry(*image).or_default().insert(*id);
                 }
+                material_to_images.insert(*id, material_images);
             }
             AssetEvent::Removed { id } => {
-                for image in material_to_images.remove(id).unwrap_or_default() {
-                    image_to_materials.get_mut(&image).unwrap().remove(id);
-                }
+                unlink(id, &mut image_to_materials, &mut material_to_images)
             }
             _ => {}
         }
@@ -12,7 +12,10 @@ use bevy_rapier2d::{
     plugin::{NoUserData, RapierPhysicsPlugin},
     render::RapierDebugRenderPlugin,
 };
-use components::ComponentsPlugin;
+use components::{
+    sfx::{AudioChannel, AudioGain},
+    ComponentsPlugin,
+};
 use noisy_bevy::NoisyShaderPlugin;
 use rand::Rng;
 use scenes::{main_menu::settings::GameSettings, AppScenesPlugin};
@@ -21,12 +24,11 @@ mod components;
 mod scenes;
 
 trait RandomRotation {
-    fn random() -> Quat;
+    fn random(rng: &mut impl Rng) -> Quat;
 }
 
 impl RandomRotation for Quat {
-    fn random() -> Quat {
-        let mut rng = rand::rng();
+    fn random(rng: &mut impl Rng) -> Quat {
         let u: f32 = rng.random();
         let v: f32 = rng.random();
         let w: f32 = rng.random();
@@ -53,13 +55,27 @@ fn on_resize(mut commands: Commands, mut resize_reader: EventReader<WindowResize
 
 fn apply_settings(
     settings: Res<GameSettings>,
-    mut playbacks: Query<&mut PlaybackSettings>,
+    mut playbacks: Query<(
+        &mut PlaybackSettings,
+        Option<&AudioSink>,
+        Option<&AudioChannel>,
+        Option<&AudioGain>,
+    )>,
     mut oits: Query<&mut OrderIndependentTransparencySettings>,
     mut blooms: Query<&mut Bloom>,
     mut window: Query<&mut Window>,
 ) {
-    for mut playback in playbacks.iter_mut() {
-        playback.volume = Volume::new(settings.music_volume);
+    for (mut playback, sink, channel, gain) in playbacks.iter_mut() {
+        let volume = match channel {
+            Some(AudioChannel::Effects) => settings.effects_volume,
+            Some(AudioChannel::Music) | None => settings.music_volume,
+        } * gain.map_or(1.0, |gain| gain.0);
+        playback.volume = Volume::new(volume);
+        if let Some(sink) = sink {
+            if sink.volume() != volume {
+                sink.set_volume(volume);
+            }
+        }
     }
 
     for mut oit in oits.iter_mut() {
@@ -84,6 +100,11 @@ fn apply_settings(
 }
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--headless") {
+        scenes::game::simulation::run();
+        return;
+    }
+
     App::new()
         .add_plugins((DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
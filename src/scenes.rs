@@ -1,9 +1,16 @@
 use bevy::prelude::*;
 use game::GamePlugin;
-use main_menu::{about::MainMenuAboutPlugin, settings::MainMenuSettingsPlugin, MainMenuPlugin};
+use main_menu::{
+    about::MainMenuAboutPlugin, controls::MainMenuControlsPlugin, load::MainMenuLoadPlugin,
+    settings::MainMenuSettingsPlugin, MainMenuPlugin,
+};
+use splash::SplashPlugin;
+use titles::TitlesPlugin;
 
 pub mod game;
 pub mod main_menu;
+pub mod splash;
+pub mod titles;
 
 pub struct AppScenesPlugin;
 
@@ -24,19 +31,27 @@ impl Plugin for AppScenesPlugin {
                 },
             ))
             .id();
-        app.insert_state(AppState::MainMenu)
+        app.insert_state(AppState::Splash)
             .insert_resource(AppSceneRoot {
                 world: root_world,
                 ui: root_ui,
             })
+            .add_plugins(SplashPlugin)
+            .add_systems(OnExit(AppState::Splash), cleanup)
             .add_plugins(MainMenuPlugin)
             .add_systems(OnExit(AppState::MainMenu), cleanup)
             .add_plugins(MainMenuSettingsPlugin)
             .add_systems(OnExit(AppState::MainMenuSettings), cleanup)
+            .add_plugins(MainMenuControlsPlugin)
+            .add_systems(OnExit(AppState::MainMenuControls), cleanup)
             .add_plugins(MainMenuAboutPlugin)
             .add_systems(OnExit(AppState::MainMenuAbout), cleanup)
+            .add_plugins(MainMenuLoadPlugin)
+            .add_systems(OnExit(AppState::MainMenuLoad), cleanup)
             .add_plugins(GamePlugin)
-            .add_systems(OnExit(AppState::Game), cleanup);
+            .add_systems(OnExit(AppState::Game), cleanup)
+            .add_plugins(TitlesPlugin)
+            .add_systems(OnExit(AppState::Titles), cleanup);
     }
 }
 
@@ -45,7 +60,9 @@ pub enum AppState {
     Splash,
     MainMenu,
     MainMenuSettings,
+    MainMenuControls,
     MainMenuAbout,
+    MainMenuLoad,
     Game,
     Titles,
 }
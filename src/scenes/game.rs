@@ -1,20 +1,30 @@
 use bevy::prelude::*;
 use build_material::BuildMaterialPlugin;
 use builder::{ActionState, BuilderPlugin, Enabled, HighlightState, NodeState};
+use building_model::BuildingModel;
+use buildings::{BuildingDef, Buildings, BuildingsPlugin};
+pub use buildings::{BuildingDefs, DEFAULT_BUILDINGS};
 use camera::GameCameraPlugin;
 use cargo::CargoPlugin;
-use crusher::CrusherPlugin;
-use enrichment::EnrichmentPlugin;
-use furnace::FurnacePlugin;
-use game_cursor::{GameCursor, GameCursorActive, GameCursorPlugin};
-use generator::GeneratorPlugin;
+use crusher::{Crusher, CrusherPlugin};
+use custom::CustomPlugin;
+use enrichment::{Enrichment, EnrichmentPlugin};
+use furnace::{Furnace, FurnacePlugin};
+use game_cursor::{GameCursorActive, GameCursorPlugin};
+use generator::{Generator, GeneratorPlugin};
 use hook::{Hook, HookPlugin};
 use light_consts::lux::CLEAR_SUNRISE;
-use map_state::{Cargo, MapLayer, MapNode, MapState, MapStatePlugin};
+pub use map_state::LogisticsMode;
+use map_state::{Cargo, MapNode, MapState, MapStatePlugin};
 use player::{PlayerPlugin, PlayerState};
 use primary_block::{PrimaryBlock, PrimaryBlockPlugin};
 use rock::RockPlugin;
-use room::RoomPlugin;
+use room::{Room, RoomPlugin};
+use save::{PendingLoad, SavePlugin, SaveRequest, SAVE_SLOTS, SAVE_SLOT_NAMES};
+use seed::SeedPlugin;
+use serde::{Deserialize, Serialize};
+use shield::ShieldPlugin;
+use stats::StatsPlugin;
 use strum::IntoEnumIterator;
 use ui::{
     cargo_count::GameUiCargoCount,
@@ -23,22 +33,31 @@ use ui::{
     header::GameUiHeader,
     palette::{COLOR_CONTAINER, COLOR_HEADER, COLOR_HIGHLIGHT_DARK, COLOR_TEXT},
     power_bar::GameUiPowerBar,
+    room_panel::GameUiRoomPanel,
+    stats_panel::GameUiStatsPanel,
+    toast::{GameUiToast, ShowToast},
     GameUiPlugin,
 };
+use warp::{WarpPlugin, WarpState, WARP_BATTERIES, WARP_STABLE_TIME};
 
 use crate::components::{
     game_button::GameButton,
     mouse_event::{Clicked, Dehovered, Hovered},
-    music_player::MusicPlayerPlugin,
+    music_player::{MusicPlayerPlugin, NowPlaying},
+    procedural_material::PROCEDURAL_SHADER,
+    sfx::{PlaySfx, Sfx},
 };
 
-use super::{AppSceneRoot, AppState};
+use super::{main_menu::STARS_SHADER, AppSceneRoot, AppState};
 
 mod build_material;
 mod builder;
+mod building_model;
+mod buildings;
 mod camera;
 mod cargo;
 mod crusher;
+mod custom;
 mod enrichment;
 mod furnace;
 mod game_cursor;
@@ -49,13 +68,55 @@ mod player;
 mod primary_block;
 mod rock;
 mod room;
+pub mod save;
+pub mod seed;
+mod shield;
+pub mod simulation;
+mod stats;
 pub mod ui;
+mod warp;
+
+// Loaded up front by the splash screen, each model with the material names its plugin looks up
+pub fn models() -> Vec<(&'static str, &'static [&'static str])> {
+    fn building<T: BuildingModel>() -> (&'static str, &'static [&'static str]) {
+        (T::model(), T::parts())
+    }
+
+    let mut models = vec![
+        building::<Room>(),
+        building::<PrimaryBlock>(),
+        building::<cargo::Cargo>(),
+        building::<Crusher>(),
+        building::<Enrichment>(),
+        building::<Furnace>(),
+        building::<Generator>(),
+        (hook::BASE_MODEL, &[]),
+        (hook::HEAD_MODEL, &[]),
+        (rock::ROCK_MODEL, &[rock::ROCK_MATERIAL]),
+    ];
+    // Rooms and the primary block share a model
+    models.sort();
+    models.dedup();
+    models
+}
+
+pub fn shaders() -> Vec<&'static str> {
+    vec![
+        build_material::BUILD_SHADER,
+        PROCEDURAL_SHADER,
+        rock::ROCK_SHADER,
+        room::FLOOR_SHADER,
+        camera::STARS_SHADER,
+        STARS_SHADER,
+    ]
+}
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(GameUiPlugin)
+            .add_plugins(BuildingsPlugin)
             .add_plugins(MapStatePlugin)
             .add_plugins(MusicPlayerPlugin)
             .add_plugins(GameCameraPlugin(Vec3::new(0.0, 0.0, 15.0)))
@@ -70,18 +131,35 @@ impl Plugin for GamePlugin {
             .add_plugins(GeneratorPlugin)
             .add_plugins(EnrichmentPlugin)
             .add_plugins(CrusherPlugin)
+            .add_plugins(CustomPlugin)
             .add_plugins(RockPlugin)
             .add_plugins(HookPlugin)
+            .add_plugins(ShieldPlugin)
+            .add_plugins(SavePlugin)
+            .add_plugins(SeedPlugin)
+            .add_plugins(WarpPlugin)
+            .add_plugins(StatsPlugin)
             .add_systems(OnEnter(AppState::Game), setup)
             .add_systems(
                 Update,
                 (
-                    update.run_if(resource_exists::<GameEntities>),
+                    (update, update_warp_status, update_palette)
+                        .run_if(resource_exists::<GameEntities>),
                     process_spawn_requests,
                 )
                     .chain(),
             )
+            .add_systems(
+                Update,
+                (update_clock, warn_low_power)
+                    .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            )
+            .add_observer(|trigger: Trigger<NowPlaying>, mut commands: Commands| {
+                commands.trigger(ShowToast(format!("Now playing: {}", trigger.event().0)));
+            })
             .insert_state(GameState::Idle)
+            .insert_resource(GameClock::default())
+            .insert_resource(RunStats::default())
             .insert_resource(TooltipState::default());
     }
 }
@@ -91,11 +169,12 @@ struct GameEntities {
     game_field: Entity,
     pause_menu: Entity,
     power_bar: Entity,
+    warp_status: Entity,
     tooltip: Entity,
     tooltip_title: Entity,
     tooltip_cost: Entity,
     tooltip_desc: Entity,
-    info_thumbnail: Entity,
+    palette: Entity,
     cargo_counts: Vec<(Cargo, Entity)>,
 }
 
@@ -110,10 +189,44 @@ struct TooltipState {
 #[derive(Resource)]
 struct SpawnRequest(MapNode);
 
+#[derive(Resource, Default)]
+pub struct GameClock(pub f32);
+
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct RunStats {
+    pub rocks_harvested: u32,
+    pub rooms_built: u32,
+}
+
+fn update_clock(mut clock: ResMut<GameClock>, time: Res<Time>) {
+    clock.0 += time.delta_secs();
+}
+
+const LOW_POWER_WARNING_INTERVAL: f32 = 15.0;
+
+fn warn_low_power(
+    mut commands: Commands,
+    map_state: Res<MapState>,
+    clock: Res<GameClock>,
+    mut last_warning: Local<Option<f32>>,
+) {
+    let (available, in_use) = map_state.energy();
+    if in_use <= available {
+        *last_warning = None;
+        return;
+    }
+
+    if last_warning.map_or(true, |last| clock.0 - last >= LOW_POWER_WARNING_INTERVAL) {
+        commands.trigger(PlaySfx(Sfx::LowPower));
+        *last_warning = Some(clock.0);
+    }
+}
+
 fn process_spawn_requests(
     mut commands: Commands,
     mut next_state: ResMut<NextState<PlayerState>>,
     map_state: Res<MapState>,
+    buildings: Res<Buildings>,
     request: Option<Res<SpawnRequest>>,
 ) {
     let Some(SpawnRequest(node)) = request.as_deref() else {
@@ -123,7 +236,7 @@ fn process_spawn_requests(
     commands.remove_resource::<SpawnRequest>();
 
     let mut success = true;
-    for (cargo, count) in node.recipe() {
+    for (cargo, count) in buildings.recipe(node) {
         if map_state.cargo_count(cargo.clone()).0 < count {
             success = false;
             break;
@@ -136,32 +249,32 @@ fn process_spawn_requests(
     }
 }
 
-fn item_spawner(node: MapNode) -> impl Fn(&mut ChildBuilder) {
+fn item_spawner(def: BuildingDef) -> impl Fn(&mut ChildBuilder) {
     move |parent| {
         parent
             .spawn(
-                GameUiContainerItem::new(node.name())
+                GameUiContainerItem::new(&def.name)
                     .button()
-                    .image(node.thumbnail()),
+                    .image(&def.thumbnail),
             )
             .observe({
-                let node = node.clone();
+                let node = def.node.clone();
                 move |_: Trigger<Clicked>, mut commands: Commands| {
                     commands.insert_resource(SpawnRequest(node.clone()));
                 }
             })
             .observe({
-                let node = node.clone();
+                let def = def.clone();
                 move |_: Trigger<Hovered>, mut tooltip: ResMut<TooltipState>| {
                     tooltip.visible = true;
-                    tooltip.title = node.name().to_string();
-                    tooltip.cost = node
-                        .recipe()
-                        .into_iter()
+                    tooltip.title = def.name.clone();
+                    tooltip.cost = def
+                        .recipe
+                        .iter()
                         .map(|(cargo, cnt)| format!("{}: {cnt}", cargo.name()))
                         .collect::<Vec<_>>()
                         .join("\n");
-                    tooltip.desc = node.desc().to_string();
+                    tooltip.desc = def.desc.clone();
                 }
             })
             .observe(|_: Trigger<Dehovered>, mut tooltip: ResMut<TooltipState>| {
@@ -173,11 +286,23 @@ fn item_spawner(node: MapNode) -> impl Fn(&mut ChildBuilder) {
 fn setup(
     mut commands: Commands,
     mut map_state: ResMut<MapState>,
+    mut clock: ResMut<GameClock>,
+    mut run_stats: ResMut<RunStats>,
+    mut warp_state: ResMut<WarpState>,
+    buildings: Res<Buildings>,
+    logistics: Res<LogisticsMode>,
     root_entity: Res<AppSceneRoot>,
+    pending_load: Option<Res<PendingLoad>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     next_state.set(GameState::Idle);
 
+    *map_state = MapState::default();
+    map_state.set_logistics(logistics.0);
+    *clock = GameClock::default();
+    *run_stats = RunStats::default();
+    *warp_state = WarpState::default();
+
     let directional_light = |x, y| {
         (
             DirectionalLight {
@@ -194,15 +319,17 @@ fn setup(
         root.spawn(directional_light(1.0, -1.0));
         root.spawn(directional_light(-1.0, 1.0));
         root.spawn(directional_light(-1.0, -1.0));
-        root.spawn((
-            PrimaryBlock,
-            NodeState {
-                action: ActionState::Idle,
-                highlight: HighlightState::None,
-            },
-        ))
-        .with_child((Hook(false), Enabled));
-        map_state.add_primary_block(0, 0);
+        if pending_load.is_none() {
+            root.spawn((
+                PrimaryBlock,
+                NodeState {
+                    action: ActionState::Idle,
+                    highlight: HighlightState::None,
+                },
+            ))
+            .with_child((Hook(false), Enabled));
+            map_state.add_primary_block(0, 0);
+        }
     });
 
     // commands.spawn((
@@ -221,7 +348,8 @@ fn setup(
     let mut game_field = Entity::PLACEHOLDER;
     let mut pause_menu = Entity::PLACEHOLDER;
     let mut power_bar = Entity::PLACEHOLDER;
-    let mut info_thumbnail = Entity::PLACEHOLDER;
+    let mut warp_status = Entity::PLACEHOLDER;
+    let mut palette = Entity::PLACEHOLDER;
     let mut cargo_counts = vec![];
 
     let mut spawn_tooltip = |parent: &mut ChildBuilder| {
@@ -314,6 +442,23 @@ fn setup(
                     ))
                     .with_children(|parent| {
                         power_bar = parent.spawn(GameUiPowerBar::new()).id();
+                        warp_status = parent
+                            .spawn((
+                                Node {
+                                    width: Val::Px(360.0),
+                                    flex_shrink: 0.0,
+                                    align_self: AlignSelf::Center,
+                                    margin: UiRect::left(Val::Px(20.0)),
+                                    ..Default::default()
+                                },
+                                Text::default(),
+                                TextColor(COLOR_TEXT),
+                                TextFont {
+                                    font_size: 20.0,
+                                    ..Default::default()
+                                },
+                            ))
+                            .id();
                     });
                 game_field = parent
                     .spawn(Node {
@@ -349,6 +494,26 @@ fn setup(
                                     },
                                 );
                             })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn(Node {
+                                        column_gap: Val::Px(10.0),
+                                        ..Default::default()
+                                    })
+                                    .with_children(|parent| {
+                                        for slot in 0..SAVE_SLOTS {
+                                            parent
+                                                .spawn(GameButton::new(SAVE_SLOT_NAMES[slot], 60.0))
+                                                .observe(
+                                                    move |_: Trigger<Clicked>,
+                                                          mut commands: Commands| {
+                                                        commands
+                                                            .insert_resource(SaveRequest(slot));
+                                                    },
+                                                );
+                                        }
+                                    });
+                            })
                             .with_children(|parent| {
                                 parent.spawn(GameButton::new("Exit", 0.0)).observe(
                                     |_: Trigger<Clicked>, mut next: ResMut<NextState<AppState>>| {
@@ -359,25 +524,7 @@ fn setup(
                             .id();
                     })
                     .id();
-                parent
-                    .spawn((
-                        Node {
-                            width: Val::Percent(100.0),
-                            flex_shrink: 0.0,
-                            display: Display::None,
-                            ..Default::default()
-                        },
-                        BackgroundColor(COLOR_CONTAINER),
-                    ))
-                    .with_children(|parent| {
-                        info_thumbnail = parent
-                            .spawn(Node {
-                                width: Val::Px(100.0),
-                                height: Val::Px(100.0),
-                                ..Default::default()
-                            })
-                            .id();
-                    });
+                parent.spawn(GameUiRoomPanel);
             });
     };
 
@@ -400,15 +547,11 @@ fn setup(
             },))
             .with_children(|parent| {
                 parent.spawn(GameUiHeader::new("Build"));
-                parent
-                    .spawn(GameUiContainer)
-                    .with_children(item_spawner(MapNode::EmptyRoom))
-                    .with_children(item_spawner(MapNode::Furnace))
-                    .with_children(item_spawner(MapNode::Cargo))
-                    .with_children(item_spawner(MapNode::Crusher))
-                    .with_children(item_spawner(MapNode::Generator))
-                    .with_children(item_spawner(MapNode::Hook))
-                    .with_children(item_spawner(MapNode::Enrichment));
+                let mut container = parent.spawn(GameUiContainer);
+                for def in buildings.buildable() {
+                    container.with_children(item_spawner(def.clone()));
+                }
+                palette = container.id();
             });
     };
 
@@ -417,17 +560,20 @@ fn setup(
         spawn_cargo_counts(root);
         spawn_center(root);
         spawn_build_options(root);
+        root.spawn(GameUiStatsPanel);
+        root.spawn(GameUiToast);
     });
 
     commands.insert_resource(GameEntities {
         power_bar,
+        warp_status,
         game_field,
         pause_menu,
         tooltip,
         tooltip_title,
         tooltip_cost,
         tooltip_desc,
-        info_thumbnail,
+        palette,
         cargo_counts,
     });
 }
@@ -444,15 +590,12 @@ fn update(
     window: Query<&Window>,
     tooltip_state: Res<TooltipState>,
     map_state: Res<MapState>,
-    player_state: Res<State<PlayerState>>,
-    game_cursor: Option<Res<GameCursor>>,
-    assets: Res<AssetServer>,
     mut cargo_counts: Query<&mut GameUiCargoCount>,
     game_state: Res<State<GameState>>,
 ) {
     if let Ok(mut visibility) = visibilities.get_mut(state.pause_menu) {
         *visibility = match game_state.get() {
-            GameState::Idle => Visibility::Hidden,
+            GameState::Idle | GameState::Warp => Visibility::Hidden,
             GameState::Pause => Visibility::Inherited,
         };
     }
@@ -502,18 +645,6 @@ fn update(
         text.0 = tooltip_state.desc.clone();
     }
 
-    if let Some(mut thumbnail) = commands.get_entity(state.info_thumbnail) {
-        if let Some((x, y)) = match (game_cursor.as_ref(), player_state.get()) {
-            (_, PlayerState::Interact(ix, iy)) => Some((*ix, *iy)),
-            (Some(game_cursor), PlayerState::Idle) => Some((game_cursor.x, game_cursor.y)),
-            _ => None,
-        } {
-            if let Some(node) = map_state.node(x, y, MapLayer::Main) {
-                thumbnail.insert(ImageNode::new(assets.load(node.thumbnail())));
-            }
-        }
-    }
-
     for (cargo, count) in &state.cargo_counts {
         if let Ok(mut count) = cargo_counts.get_mut(*count) {
             let (cur, max) = map_state.cargo_count(cargo.clone());
@@ -523,8 +654,36 @@ fn update(
     }
 }
 
+fn update_palette(mut commands: Commands, state: Res<GameEntities>, buildings: Res<Buildings>) {
+    if !buildings.is_changed() {
+        return;
+    }
+
+    let Some(mut palette) = commands.get_entity(state.palette) else {
+        return;
+    };
+    palette.despawn_descendants();
+    for def in buildings.buildable() {
+        palette.with_children(item_spawner(def.clone()));
+    }
+}
+
+fn update_warp_status(
+    state: Res<GameEntities>,
+    warp_state: Res<WarpState>,
+    mut texts: Query<&mut Text>,
+) {
+    if let Ok(mut text) = texts.get_mut(state.warp_status) {
+        text.0 = format!(
+            "Warp charge: {:.1}/{WARP_BATTERIES}\nStable power: {:.0}/{WARP_STABLE_TIME}s",
+            warp_state.charge, warp_state.stable
+        );
+    }
+}
+
 #[derive(States, Debug, Hash, PartialEq, Eq, Clone)]
 enum GameState {
     Idle,
     Pause,
+    Warp,
 }
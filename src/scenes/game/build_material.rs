@@ -4,7 +4,12 @@ use bevy::{
     render::render_resource::{AsBindGroup, ShaderRef, ShaderType},
 };
 
-use crate::components::material_modifier::MaterialModifierPlugin;
+use crate::components::{
+    material_modifier::MaterialModifierPlugin,
+    update_material_textures::UpdateMaterialTexturesPlugin,
+};
+
+pub const BUILD_SHADER: &str = "build.wgsl";
 
 pub struct BuildMaterialPlugin;
 
@@ -14,7 +19,8 @@ impl Plugin for BuildMaterialPlugin {
             .add_plugins(MaterialModifierPlugin::<
                 StandardMaterial,
                 ExtendedBuildMaterial,
-            >::default());
+            >::default())
+            .add_plugins(UpdateMaterialTexturesPlugin::<ExtendedBuildMaterial>::default());
     }
 }
 
@@ -35,6 +41,6 @@ pub type ExtendedBuildMaterial = ExtendedMaterial<StandardMaterial, BuildMateria
 
 impl MaterialExtension for BuildMaterial {
     fn fragment_shader() -> ShaderRef {
-        "build.wgsl".into()
+        BUILD_SHADER.into()
     }
 }
@@ -2,15 +2,19 @@ use bevy::{pbr::ExtendedMaterial, prelude::*};
 
 use crate::{
     components::{
-        material_modifier::MaterialModifier, procedural_material::ExtendedProceduralMaterial,
+        material_modifier::MaterialModifier,
+        procedural_material::ExtendedProceduralMaterial,
+        sfx::{PlaySfx, Sfx},
     },
     scenes::AppSceneRoot,
 };
 
 use super::{
     build_material::{BuildMaterial, BuildMaterialSettings, ExtendedBuildMaterial},
+    buildings::Buildings,
     cargo::Cargo,
     crusher::Crusher,
+    custom::Custom,
     enrichment::Enrichment,
     furnace::Furnace,
     game_cursor::{CursorLayer, GameCursor},
@@ -20,6 +24,8 @@ use super::{
     player::PlayerState,
     primary_block::PrimaryBlock,
     room::Room,
+    shield::Shield,
+    RunStats,
 };
 
 pub struct BuilderPlugin;
@@ -40,6 +46,7 @@ pub enum HighlightState {
     Green,
     Orange,
     Red,
+    Unpowered,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -58,12 +65,30 @@ pub struct NodeState {
 #[derive(Component)]
 pub struct Ready;
 
+#[derive(Component, Clone, Copy, PartialEq)]
+pub struct Damage(pub f32);
+
 #[derive(Component)]
 pub struct Enabled;
 
 #[derive(Resource)]
 pub struct BuildEntity(pub Entity);
 
+pub fn spawn_node<'a>(commands: &'a mut Commands, node: &MapNode) -> EntityCommands<'a> {
+    match node {
+        MapNode::PrimaryBlock => commands.spawn(PrimaryBlock),
+        MapNode::EmptyRoom => commands.spawn(Room),
+        MapNode::Furnace => commands.spawn(Furnace),
+        MapNode::Generator => commands.spawn(Generator),
+        MapNode::Crusher => commands.spawn(Crusher),
+        MapNode::Cargo => commands.spawn(Cargo),
+        MapNode::Hook => commands.spawn(Hook(true)),
+        MapNode::Enrichment => commands.spawn(Enrichment),
+        MapNode::Shield => commands.spawn(Shield),
+        MapNode::Custom(name) => commands.spawn(Custom(name.clone())),
+    }
+}
+
 fn transit_state(
     mut commands: Commands,
     mut transition_events: EventReader<StateTransitionEvent<PlayerState>>,
@@ -86,6 +111,8 @@ fn construct(
     mut commands: Commands,
     mut nodes: Query<(&mut NodeState, &mut Transform)>,
     mut map_state: ResMut<MapState>,
+    mut run_stats: ResMut<RunStats>,
+    buildings: Res<Buildings>,
     mut next_player_state: ResMut<NextState<PlayerState>>,
     player_state: Res<State<PlayerState>>,
     root_entity: Res<AppSceneRoot>,
@@ -106,22 +133,13 @@ fn construct(
     };
 
     let Some(BuildEntity(entity)) = build_entity.as_deref() else {
-        let entity = match node {
-            MapNode::PrimaryBlock => commands.spawn(PrimaryBlock),
-            MapNode::EmptyRoom => commands.spawn(Room),
-            MapNode::Furnace => commands.spawn(Furnace),
-            MapNode::Generator => commands.spawn(Generator),
-            MapNode::Crusher => commands.spawn(Crusher),
-            MapNode::Cargo => commands.spawn(Cargo),
-            MapNode::Hook => commands.spawn(Hook(true)),
-            MapNode::Enrichment => commands.spawn(Enrichment),
-        }
-        .insert(NodeState {
-            action: ActionState::Idle,
-            highlight: HighlightState::Green,
-        })
-        .insert(Transform::from_xyz(game_cursor.fx, game_cursor.fy, 0.0))
-        .id();
+        let entity = spawn_node(&mut commands, node)
+            .insert(NodeState {
+                action: ActionState::Idle,
+                highlight: HighlightState::Green,
+            })
+            .insert(Transform::from_xyz(game_cursor.fx, game_cursor.fy, 0.0))
+            .id();
         commands.entity(root_entity.world).add_child(entity);
         commands.insert_resource(BuildEntity(entity));
         return;
@@ -151,10 +169,12 @@ fn construct(
     if available && game_cursor.just_pressed {
         node_state.action = ActionState::Construct(time.elapsed_secs());
         node_state.highlight = HighlightState::None;
+        commands.trigger(PlaySfx(Sfx::Construct));
         next_player_state.set(PlayerState::Idle);
         commands.remove_resource::<BuildEntity>();
         map_state.add_room(game_cursor.x, game_cursor.y, node.clone());
-        for (cargo, count) in node.recipe() {
+        run_stats.rooms_built += 1;
+        for (cargo, count) in buildings.recipe(node) {
             map_state.harvest(cargo, -count);
         }
     }
@@ -214,8 +234,11 @@ fn destruct(
     }
 }
 
-fn update_material(mut commands: Commands, nodes: Query<(Entity, &NodeState), With<Ready>>) {
-    for (entity, node_state) in nodes.iter() {
+fn update_material(
+    mut commands: Commands,
+    nodes: Query<(Entity, &NodeState, Option<&Damage>), With<Ready>>,
+) {
+    for (entity, node_state, damage) in nodes.iter() {
         let mut entity = commands.entity(entity);
 
         entity
@@ -235,6 +258,18 @@ fn update_material(mut commands: Commands, nodes: Query<(Entity, &NodeState), Wi
             mat
         };
 
+        let damage = damage.map_or(0.0, |Damage(damage)| *damage);
+        let with_damage = move |mut mat: StandardMaterial| {
+            mat.base_color = mat
+                .base_color
+                .to_srgba()
+                .mix(&Srgba::rgb(0.35, 0.08, 0.02), damage * 0.6)
+                .with_alpha(mat.base_color.alpha())
+                .into();
+            mat.perceptual_roughness = mat.perceptual_roughness.max(damage);
+            mat
+        };
+
         let procedural_inactive =
             MaterialModifier::new(move |mut mat: ExtendedProceduralMaterial| {
                 mat.base.base_color.set_alpha(0.5);
@@ -243,10 +278,15 @@ fn update_material(mut commands: Commands, nodes: Query<(Entity, &NodeState), Wi
             });
 
         match (node_state.action, node_state.highlight) {
-            (ActionState::Idle, HighlightState::None) => {}
+            (ActionState::Idle, HighlightState::None) => {
+                if damage > 0.0 {
+                    entity.insert(MaterialModifier::new(with_damage));
+                }
+            }
             (ActionState::Idle, HighlightState::White) => {
                 entity
-                    .insert(MaterialModifier::new(move |mut mat: StandardMaterial| {
+                    .insert(MaterialModifier::new(move |mat: StandardMaterial| {
+                        let mut mat = with_damage(mat);
                         mat.emissive += highlight_white;
                         mat.alpha_mode = AlphaMode::Blend;
                         mat
@@ -258,6 +298,21 @@ fn update_material(mut commands: Commands, nodes: Query<(Entity, &NodeState), Wi
                         },
                     ));
             }
+            (ActionState::Idle, HighlightState::Unpowered) => {
+                entity
+                    .insert(MaterialModifier::new(move |mat: StandardMaterial| {
+                        let mut mat = with_damage(mat);
+                        mat.base_color = mat.base_color.darker(0.5);
+                        mat.emissive = LinearRgba::NONE;
+                        mat
+                    }))
+                    .insert(MaterialModifier::new(
+                        |mut mat: ExtendedProceduralMaterial| {
+                            mat.base.emissive = LinearRgba::NONE;
+                            mat
+                        },
+                    ));
+            }
             (ActionState::Idle, HighlightState::Green) => {
                 entity
                     .insert(procedural_inactive)
@@ -325,7 +380,7 @@ fn update_material(mut commands: Commands, nodes: Query<(Entity, &NodeState), Wi
 
 fn update(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut NodeState, &Transform)>,
+    mut query: Query<(Entity, &mut NodeState, &Transform, Option<&Damage>)>,
     build_entity: Option<Res<BuildEntity>>,
     game_cursor: Option<Res<GameCursor>>,
     map_state: Res<MapState>,
@@ -334,7 +389,7 @@ fn update(
 ) {
     let elapsed = time.elapsed_secs();
 
-    for (entity, mut state, transform) in query.iter_mut() {
+    for (entity, mut state, transform, damage) in query.iter_mut() {
         let enabled = state.action == ActionState::Idle
             && !build_entity.as_ref().is_some_and(|e| e.0 == entity);
 
@@ -366,11 +421,23 @@ fn update(
                     && !matches!(state.action, ActionState::Destruct(_))
                 {
                     state.action = ActionState::Destruct(time.elapsed_secs());
+                    commands.trigger(PlaySfx(Sfx::Destruct));
                 }
 
                 if enabled {
+                    let health = map_state.health(x, y);
+                    if health < 1.0 {
+                        if damage != Some(&Damage(1.0 - health)) {
+                            commands.entity(entity).insert(Damage(1.0 - health));
+                        }
+                    } else if damage.is_some() {
+                        commands.entity(entity).remove::<Damage>();
+                    }
+
                     state.highlight = if is_selected {
                         HighlightState::White
+                    } else if map_state.power(x, y) < 1.0 {
+                        HighlightState::Unpowered
                     } else {
                         HighlightState::None
                     };
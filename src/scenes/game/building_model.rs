@@ -0,0 +1,243 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    gltf::{Gltf, GltfMaterialName},
+    prelude::*,
+    scene::SceneInstanceReady,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::scenes::AppState;
+
+use super::{
+    builder::Ready,
+    game_cursor::{CursorLayer, GameCursor},
+    map_state::MapState,
+    GameState,
+};
+
+const ACTIVITY_SMOOTHING: f32 = 2.0;
+
+// A building declares its model here, its plugin only adds the behavior systems
+pub trait BuildingModel: Component {
+    fn model() -> &'static str;
+
+    fn parts() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn collider() -> Option<Collider> {
+        None
+    }
+}
+
+pub struct BuildingModelPlugin<Building: BuildingModel> {
+    _pd: PhantomData<Building>,
+}
+
+impl<Building: BuildingModel> Default for BuildingModelPlugin<Building> {
+    fn default() -> Self {
+        Self {
+            _pd: PhantomData::default(),
+        }
+    }
+}
+
+impl<Building: BuildingModel> Plugin for BuildingModelPlugin<Building> {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ModelLoaderPlugin>() {
+            app.add_plugins(ModelLoaderPlugin);
+        }
+
+        app.add_systems(
+            Update,
+            declare::<Building>
+                .before(load)
+                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+        );
+    }
+}
+
+struct ModelLoaderPlugin;
+
+impl Plugin for ModelLoaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (load, check_failed, update_activity)
+                .chain()
+                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+        )
+        .add_observer(capture_parts);
+    }
+}
+
+#[derive(Component)]
+pub struct LoadModel {
+    pub path: String,
+    pub parts: &'static [&'static str],
+}
+
+// Descendants of the model matching the declared material names
+#[derive(Component, Default)]
+pub struct ModelParts(Vec<(&'static str, Entity)>);
+
+impl ModelParts {
+    pub fn get<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Entity> + 'a {
+        self.0
+            .iter()
+            .filter(move |(part, _)| *part == name)
+            .map(|(_, entity)| *entity)
+    }
+}
+
+// Parts get their own copy of the material, so the emissive of one
+// building can change without touching every other instance of the model
+#[derive(Component)]
+pub struct PartMaterial {
+    handle: Handle<StandardMaterial>,
+    emissive: LinearRgba,
+}
+
+impl PartMaterial {
+    pub fn set_emissive(&self, materials: &mut Assets<StandardMaterial>, intensity: f32) {
+        let emissive = self.emissive * intensity;
+        if materials
+            .get(&self.handle)
+            .is_some_and(|material| material.emissive != emissive)
+        {
+            if let Some(material) = materials.get_mut(&self.handle) {
+                material.emissive = emissive;
+            }
+        }
+    }
+}
+
+// MapState::activity of the room the building stands in, smoothed so
+// animations ease in and out
+#[derive(Component, Default)]
+pub struct Activity(pub f32);
+
+#[derive(Component)]
+enum ModelState {
+    Loading(Handle<Gltf>),
+    Done,
+}
+
+fn declare<Building: BuildingModel>(
+    mut commands: Commands,
+    buildings: Query<Entity, (With<Building>, Without<LoadModel>)>,
+) {
+    for entity in buildings.iter() {
+        let mut entity = commands.entity(entity);
+        entity.insert(LoadModel {
+            path: Building::model().to_string(),
+            parts: Building::parts(),
+        });
+        if let Some(collider) = Building::collider() {
+            entity.insert((
+                collider,
+                ActiveEvents::COLLISION_EVENTS,
+                ActiveCollisionTypes::STATIC_STATIC,
+            ));
+        }
+    }
+}
+
+fn load(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    models: Query<(Entity, &LoadModel), Without<ModelState>>,
+) {
+    for (entity, model) in models.iter() {
+        commands.entity(entity).insert((
+            SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model.path.clone()))),
+            ModelState::Loading(asset_server.load(model.path.clone())),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+// A building without a model still works, it is just not visible
+fn check_failed(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    models: Query<(Entity, &LoadModel, &ModelState)>,
+) {
+    for (entity, model, state) in models.iter() {
+        let ModelState::Loading(gltf) = state else {
+            continue;
+        };
+        if asset_server.load_state(gltf).is_failed() {
+            error!("Failed to load model {}", model.path);
+            commands.entity(entity).insert((
+                Ready,
+                ModelState::Done,
+                ModelParts::default(),
+                Activity::default(),
+            ));
+        }
+    }
+}
+
+fn capture_parts(
+    trigger: Trigger<SceneInstanceReady>,
+    mut commands: Commands,
+    models: Query<(&LoadModel, &ModelState)>,
+    children: Query<&Children>,
+    gltf_materials: Query<(&GltfMaterialName, Option<&MeshMaterial3d<StandardMaterial>>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let entity = trigger.entity();
+    let Ok((model, ModelState::Loading(_))) = models.get(entity) else {
+        return;
+    };
+
+    let mut parts = vec![];
+    for child in children.iter_descendants(entity) {
+        let Ok((GltfMaterialName(name), material)) = gltf_materials.get(child) else {
+            continue;
+        };
+        let Some(part) = model.parts.iter().find(|part| **part == name.as_str()) else {
+            continue;
+        };
+        parts.push((*part, child));
+
+        let Some(material) = material.and_then(|m| materials.get(&m.0)).cloned() else {
+            continue;
+        };
+        let emissive = material.emissive;
+        let handle = materials.add(material);
+        commands.entity(child).insert((
+            MeshMaterial3d(handle.clone()),
+            PartMaterial { handle, emissive },
+        ));
+    }
+    for part in model.parts {
+        if !parts.iter().any(|(name, _)| name == part) {
+            warn!("{} has no material named \"{part}\"", model.path);
+        }
+    }
+
+    commands.entity(entity).insert((
+        Ready,
+        ModelState::Done,
+        ModelParts(parts),
+        Activity::default(),
+        Visibility::Inherited,
+    ));
+}
+
+fn update_activity(
+    mut buildings: Query<(&mut Activity, &Transform)>,
+    map_state: Res<MapState>,
+    time: Res<Time>,
+) {
+    let t = 1.0 - (-time.delta_secs() * ACTIVITY_SMOOTHING).exp();
+    for (mut activity, transform) in buildings.iter_mut() {
+        let Vec2 { x, y } = transform.translation.xy();
+        let IVec2 { x, y } = GameCursor::world_to_game(x, y, CursorLayer::Room);
+        let target = map_state.activity(x, y);
+        activity.0 += (target - activity.0) * t;
+    }
+}
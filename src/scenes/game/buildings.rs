@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    asset::{io::Reader, AssetLoadFailedEvent, AssetLoader, LoadContext},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use super::map_state::{Cargo, MapNode, MapState, PowerPriority};
+
+pub const DEFAULT_BUILDINGS: &str = "default.buildings.ron";
+
+pub struct BuildingsPlugin;
+
+impl Plugin for BuildingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<BuildingDefs>()
+            .register_asset_loader(BuildingDefsLoader)
+            .insert_resource(Buildings::default())
+            .add_systems(Startup, load)
+            .add_systems(PreUpdate, update);
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Process {
+    pub from: Vec<Cargo>,
+    pub to: Cargo,
+    pub speed: f32,
+    pub ratio: f32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BuildingDef {
+    pub node: MapNode,
+    pub name: String,
+    pub desc: String,
+    pub thumbnail: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub buildable: bool,
+    #[serde(default)]
+    pub recipe: BTreeMap<Cargo, f32>,
+    #[serde(default)]
+    pub energy_use: f32,
+    #[serde(default)]
+    pub energy_output: f32,
+    #[serde(default)]
+    pub priority: PowerPriority,
+    #[serde(default)]
+    pub storage: BTreeMap<Cargo, f32>,
+    #[serde(default)]
+    pub processes: Vec<Process>,
+}
+
+#[derive(Asset, TypePath, Deserialize)]
+pub struct BuildingDefs {
+    pub buildings: Vec<BuildingDef>,
+}
+
+#[derive(Default)]
+struct BuildingDefsLoader;
+
+impl AssetLoader for BuildingDefsLoader {
+    type Asset = BuildingDefs;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &(),
+        _: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["buildings.ron"]
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Buildings {
+    defs: HashMap<MapNode, BuildingDef>,
+    order: Vec<MapNode>,
+}
+
+impl Buildings {
+    pub fn from_defs(defs: &BuildingDefs) -> Self {
+        Self {
+            defs: defs
+                .buildings
+                .iter()
+                .map(|def| (def.node.clone(), def.clone()))
+                .collect(),
+            order: defs.buildings.iter().map(|def| def.node.clone()).collect(),
+        }
+    }
+
+    pub fn get(&self, node: &MapNode) -> Option<&BuildingDef> {
+        self.defs.get(node)
+    }
+
+    pub fn buildable(&self) -> impl Iterator<Item = &BuildingDef> {
+        self.order
+            .iter()
+            .filter_map(|node| self.defs.get(node))
+            .filter(|def| def.buildable)
+    }
+
+    pub fn recipe(&self, node: &MapNode) -> BTreeMap<Cargo, f32> {
+        self.get(node)
+            .map(|def| def.recipe.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Resource)]
+struct BuildingDefsHandle(Handle<BuildingDefs>);
+
+fn load(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BuildingDefsHandle(asset_server.load(DEFAULT_BUILDINGS)));
+}
+
+fn update(
+    mut events: EventReader<AssetEvent<BuildingDefs>>,
+    mut failed: EventReader<AssetLoadFailedEvent<BuildingDefs>>,
+    mut buildings: ResMut<Buildings>,
+    mut map_state: ResMut<MapState>,
+    handle: Option<Res<BuildingDefsHandle>>,
+    defs: Res<Assets<BuildingDefs>>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+
+    for event in events.read() {
+        if event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0) {
+            if let Some(defs) = defs.get(&handle.0) {
+                info!("Loaded {} buildings", defs.buildings.len());
+                *buildings = Buildings::from_defs(defs);
+                map_state.invalidate_routes();
+            }
+        }
+    }
+
+    // A broken file keeps the previous definitions, which are empty on startup
+    for event in failed.read() {
+        if event.id == handle.0.id() {
+            error!("Failed to load {}: {}", event.path, event.error);
+        }
+    }
+}
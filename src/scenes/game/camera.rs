@@ -2,16 +2,30 @@ use bevy::{
     core_pipeline::{
         bloom::Bloom, oit::OrderIndependentTransparencySettings, tonemapping::DebandDither,
     },
-    input::mouse::MouseWheel,
+    input::mouse::{MouseMotion, MouseWheel},
     prelude::*,
 };
 
 use crate::{
-    components::background::{BackgroundPluginSettings, RenderBackground},
+    components::{
+        background::{BackgroundPluginSettings, RenderBackground},
+        input_map::{Action, Actions},
+    },
     scenes::{AppSceneRoot, AppState},
 };
 
-use super::GameState;
+use super::{
+    game_cursor::{CursorLayer, GameCursor},
+    map_state::MapState,
+    player::PlayerState,
+    seed::GameSeed,
+    GameState,
+};
+
+pub const STARS_SHADER: &str = "stars.wgsl";
+const PAN_SPEED: f32 = 1.0;
+const EDGE_SCROLL_MARGIN: f32 = 8.0;
+const ZOOM_SPEED: f32 = 10.0;
 
 pub struct GameCameraPlugin(pub Vec3);
 
@@ -33,9 +47,18 @@ impl Plugin for GameCameraPlugin {
 #[derive(Resource, Deref, DerefMut)]
 struct TargetPos(Vec3);
 
-fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>, target_pos: Res<TargetPos>) {
+fn setup(
+    mut commands: Commands,
+    root_entity: Res<AppSceneRoot>,
+    mut target_pos: ResMut<TargetPos>,
+    seed: Res<GameSeed>,
+) {
+    target_pos.x = 0.0;
+    target_pos.y = 0.0;
+
     commands.insert_resource(BackgroundPluginSettings {
-        shader: "stars.wgsl".to_string(),
+        shader: STARS_SHADER.to_string(),
+        seed: seed.background(),
     });
 
     commands.entity(root_entity.world).with_child((
@@ -57,22 +80,130 @@ fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>, target_pos: Res
     ));
 }
 
+// Point on the ship plane under the viewport position, as seen from the target
+fn ground_point(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    target: Vec3,
+    viewport_pos: Vec2,
+) -> Option<Vec2> {
+    let ray = camera
+        .viewport_to_world(camera_transform, viewport_pos)
+        .ok()?;
+    if ray.direction.z >= 0.0 {
+        return None;
+    }
+    Some(target.xy() + ray.direction.xy() * target.z / -ray.direction.z)
+}
+
 fn camera_control(
     mut wheel: EventReader<MouseWheel>,
+    mut motion: EventReader<MouseMotion>,
     mut target_pos: ResMut<TargetPos>,
-    mut camera: Query<Option<&mut Transform>, With<Camera3d>>,
+    mut camera: Query<(&Camera, &GlobalTransform, &mut Transform), With<Camera3d>>,
+    window: Query<&Window>,
+    actions: Actions,
+    player_state: Res<State<PlayerState>>,
+    map_state: Res<MapState>,
     time: Res<Time>,
 ) {
-    let Ok(Some(mut camera)) = camera.get_single_mut() else {
+    let Ok((camera, camera_transform, mut transform)) = camera.get_single_mut() else {
         return;
     };
 
-    for event in wheel.read() {
-        **target_pos = **target_pos - Vec3::new(0.0, 0.0, event.y);
+    let window = window.get_single().ok();
+    let cursor_pos = window.and_then(Window::cursor_position);
+
+    let mut zoom = wheel.read().map(|event| event.y).sum::<f32>();
+    if actions.pressed(Action::ZoomIn) {
+        zoom += ZOOM_SPEED * time.delta_secs();
+    }
+    if actions.pressed(Action::ZoomOut) {
+        zoom -= ZOOM_SPEED * time.delta_secs();
     }
 
-    target_pos.z = target_pos.z.max(2.5).min(40.0);
+    if zoom != 0.0 {
+        let z = (target_pos.z - zoom).max(2.5).min(40.0);
+        let cursor =
+            cursor_pos.and_then(|pos| ground_point(camera, camera_transform, **target_pos, pos));
+        if let Some(cursor) = cursor {
+            let xy = cursor + (target_pos.xy() - cursor) * z / target_pos.z;
+            target_pos.x = xy.x;
+            target_pos.y = xy.y;
+        }
+        target_pos.z = z;
+    }
+
+    if actions.pressed(Action::PanDrag) {
+        for event in motion.read() {
+            let Some(pos) = cursor_pos else {
+                continue;
+            };
+            let (Some(from), Some(to)) = (
+                ground_point(camera, camera_transform, **target_pos, pos - event.delta),
+                ground_point(camera, camera_transform, **target_pos, pos),
+            ) else {
+                continue;
+            };
+            let xy = target_pos.xy() - (to - from);
+            target_pos.x = xy.x;
+            target_pos.y = xy.y;
+        }
+    } else {
+        motion.clear();
+    }
+
+    let mut dir = Vec2::ZERO;
+    for (action, step) in [
+        (Action::PanUp, Vec2::Y),
+        (Action::PanDown, Vec2::NEG_Y),
+        (Action::PanLeft, Vec2::NEG_X),
+        (Action::PanRight, Vec2::X),
+    ] {
+        if actions.pressed(action) {
+            dir += step;
+        }
+    }
+
+    if let (Some(window), Some(pos)) = (window, cursor_pos) {
+        if window.focused {
+            if pos.x <= EDGE_SCROLL_MARGIN {
+                dir.x -= 1.0;
+            }
+            if pos.x >= window.width() - EDGE_SCROLL_MARGIN {
+                dir.x += 1.0;
+            }
+            if pos.y <= EDGE_SCROLL_MARGIN {
+                dir.y += 1.0;
+            }
+            if pos.y >= window.height() - EDGE_SCROLL_MARGIN {
+                dir.y -= 1.0;
+            }
+        }
+    }
+
+    let xy =
+        target_pos.xy() + dir.normalize_or_zero() * PAN_SPEED * target_pos.z * time.delta_secs();
+    target_pos.x = xy.x;
+    target_pos.y = xy.y;
+
+    if actions.just_pressed(Action::Focus) {
+        if let PlayerState::Interact(x, y) = *player_state.get() {
+            let xy = GameCursor::game_to_world(x, y, CursorLayer::Room);
+            target_pos.x = xy.x;
+            target_pos.y = xy.y;
+        }
+    }
+
+    let (min, max) = map_state.get_bounds();
+    let margin = CursorLayer::Room.size() * 2.0;
+    let min = GameCursor::game_to_world(min.x, min.y, CursorLayer::Room) - margin;
+    let max = GameCursor::game_to_world(max.x, max.y, CursorLayer::Room) + margin;
+    if min.cmple(max).all() {
+        target_pos.x = target_pos.x.clamp(min.x, max.x);
+        target_pos.y = target_pos.y.clamp(min.y, max.y);
+    }
 
-    let diff = **target_pos - camera.translation;
-    camera.translation += diff * time.delta_secs() / 0.1;
+    let diff = **target_pos - transform.translation;
+    transform.translation += diff * time.delta_secs() / 0.1;
 }
@@ -2,46 +2,50 @@ use bevy::prelude::*;
 
 use crate::scenes::AppState;
 
-use super::{builder::Ready, GameState};
+use super::{
+    building_model::{BuildingModel, BuildingModelPlugin, ModelParts, PartMaterial},
+    map_state::MapState,
+    GameState,
+};
 
 pub struct CargoPlugin;
 
 impl Plugin for CargoPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (update, init)
-                .chain()
-                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
-        );
+        app.add_plugins(BuildingModelPlugin::<Cargo>::default())
+            .add_systems(
+                Update,
+                update.run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            );
     }
 }
 
+pub const LIGHT_MATERIAL: &str = "Material.010";
+
 #[derive(Component)]
 pub struct Cargo;
 
-#[derive(Component, PartialEq)]
-enum CargoState {
-    Done,
+impl BuildingModel for Cargo {
+    fn model() -> &'static str {
+        "cargo.glb"
+    }
+
+    fn parts() -> &'static [&'static str] {
+        &[LIGHT_MATERIAL]
+    }
 }
 
-fn init(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    rooms: Query<(Entity, Option<&CargoState>), With<Cargo>>,
+// Storage is shared by the whole ship, so every cargo room shows the same level
+fn update(
+    cargos: Query<&ModelParts, With<Cargo>>,
+    part_materials: Query<&PartMaterial>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    map_state: Res<MapState>,
 ) {
-    for (entity, state) in rooms.iter() {
-        match state {
-            None => {
-                commands.entity(entity).insert((
-                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("cargo.glb"))),
-                    Ready,
-                    CargoState::Done,
-                ));
-            }
-            Some(CargoState::Done) => {}
+    let fill_level = map_state.fill_level();
+    for parts in cargos.iter() {
+        for light in part_materials.iter_many(parts.get(LIGHT_MATERIAL)) {
+            light.set_emissive(&mut materials, 0.05 + fill_level * 2.0);
         }
     }
 }
-
-fn update() {}
@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+
+use crate::scenes::AppState;
+
+use super::{building_model::LoadModel, buildings::Buildings, map_state::MapNode, GameState};
+
+pub struct CustomPlugin;
+
+impl Plugin for CustomPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            init.run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Custom(pub String);
+
+// The model comes from the building list instead of a declaration, so it is set up here
+fn init(
+    mut commands: Commands,
+    buildings: Res<Buildings>,
+    rooms: Query<(Entity, &Custom), Without<LoadModel>>,
+) {
+    for (entity, Custom(name)) in rooms.iter() {
+        let Some(path) = buildings
+            .get(&MapNode::Custom(name.clone()))
+            .and_then(|def| def.model.clone())
+        else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .insert(LoadModel { path, parts: &[] });
+    }
+}
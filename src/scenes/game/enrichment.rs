@@ -2,48 +2,46 @@ use bevy::prelude::*;
 
 use crate::scenes::AppState;
 
-use super::{builder::Ready, GameState};
+use super::{
+    building_model::{Activity, BuildingModel, BuildingModelPlugin, ModelParts, PartMaterial},
+    GameState,
+};
 
 pub struct EnrichmentPlugin;
 
 impl Plugin for EnrichmentPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (update, init)
-                .chain()
-                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
-        );
+        app.add_plugins(BuildingModelPlugin::<Enrichment>::default())
+            .add_systems(
+                Update,
+                update.run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            );
     }
 }
 
+pub const CORE_MATERIAL: &str = "Material";
+
 #[derive(Component)]
 pub struct Enrichment;
 
-#[derive(Component, PartialEq)]
-enum EnrichmentState {
-    Done,
+impl BuildingModel for Enrichment {
+    fn model() -> &'static str {
+        "enrichment.glb"
+    }
+
+    fn parts() -> &'static [&'static str] {
+        &[CORE_MATERIAL]
+    }
 }
 
-fn init(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    rooms: Query<(Entity, Option<&EnrichmentState>), With<Enrichment>>,
+fn update(
+    enrichments: Query<(&ModelParts, &Activity), With<Enrichment>>,
+    part_materials: Query<&PartMaterial>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for (entity, state) in rooms.iter() {
-        match state {
-            None => {
-                commands.entity(entity).insert((
-                    SceneRoot(
-                        asset_server.load(GltfAssetLabel::Scene(0).from_asset("enrichment.glb")),
-                    ),
-                    Ready,
-                    EnrichmentState::Done,
-                ));
-            }
-            Some(EnrichmentState::Done) => {}
+    for (parts, Activity(activity)) in enrichments.iter() {
+        for core in part_materials.iter_many(parts.get(CORE_MATERIAL)) {
+            core.set_emissive(&mut materials, 0.05 + activity * 3.0);
         }
     }
 }
-
-fn update() {}
@@ -1,66 +1,47 @@
-use bevy::{gltf::GltfMaterialName, prelude::*};
+use bevy::prelude::*;
 
 use crate::scenes::AppState;
 
-use super::{builder::Ready, GameState};
+use super::{
+    building_model::{Activity, BuildingModel, BuildingModelPlugin, ModelParts, PartMaterial},
+    GameState,
+};
 
 pub struct FurnacePlugin;
 
 impl Plugin for FurnacePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (update, init)
-                .chain()
-                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
-        );
+        app.add_plugins(BuildingModelPlugin::<Furnace>::default())
+            .add_systems(
+                Update,
+                update.run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            );
     }
 }
 
+pub const GLASS_MATERIAL: &str = "Material.008";
+
 #[derive(Component)]
 pub struct Furnace;
 
-#[derive(Component, PartialEq)]
-enum FurnaceState {
-    Materials,
-    Done { glass: Entity },
+impl BuildingModel for Furnace {
+    fn model() -> &'static str {
+        "furnace.glb"
+    }
+
+    fn parts() -> &'static [&'static str] {
+        &[GLASS_MATERIAL]
+    }
 }
 
-fn init(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    rooms: Query<(Entity, Option<&FurnaceState>), With<Furnace>>,
-    children: Query<&Children>,
-    gltf_materials: Query<&GltfMaterialName>,
+fn update(
+    furnaces: Query<(&ModelParts, &Activity), With<Furnace>>,
+    part_materials: Query<&PartMaterial>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for (entity, state) in rooms.iter() {
-        match state {
-            None => {
-                commands.entity(entity).insert((
-                    SceneRoot(
-                        asset_server.load(GltfAssetLabel::Scene(0).from_asset("furnace.glb")),
-                    ),
-                    FurnaceState::Materials,
-                    Visibility::Hidden,
-                ));
-            }
-            Some(FurnaceState::Materials) => {
-                for child in children.iter_descendants(entity) {
-                    if gltf_materials
-                        .get(child)
-                        .map_or(false, |m| m.0 == "Material.008")
-                    {
-                        commands
-                            .entity(entity)
-                            .insert(Ready)
-                            .insert(FurnaceState::Done { glass: child })
-                            .insert(Visibility::Inherited);
-                    }
-                }
-            }
-            Some(FurnaceState::Done { .. }) => {}
+    for (parts, Activity(activity)) in furnaces.iter() {
+        for glass in part_materials.iter_many(parts.get(GLASS_MATERIAL)) {
+            glass.set_emissive(&mut materials, 0.1 + activity * 4.0);
         }
     }
 }
-
-fn update() {}
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 
-use crate::scenes::AppState;
+use crate::{
+    components::input_map::{Action, Actions},
+    scenes::AppState,
+};
 
 use super::GameState;
 
@@ -59,7 +62,7 @@ impl GameCursor {
 pub fn update_cursor(
     mut commands: Commands,
     active: Option<Res<GameCursorActive>>,
-    mouse: Res<ButtonInput<MouseButton>>,
+    actions: Actions,
     camera: Query<(&Camera, &GlobalTransform)>,
     window: Single<&Window>,
 ) {
@@ -87,7 +90,7 @@ pub fn update_cursor(
 
     let Vec3 { x: fx, y: fy, .. } = ray.get_point(distance);
     let IVec2 { x, y } = GameCursor::world_to_game(fx, fy, CursorLayer::Room);
-    let just_pressed = mouse.just_pressed(MouseButton::Left);
+    let just_pressed = actions.just_pressed(Action::Interact);
 
     commands.insert_resource(GameCursor {
         x,
@@ -1,66 +1,54 @@
-use bevy::{gltf::GltfMaterialName, prelude::*};
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
 
 use crate::scenes::AppState;
 
-use super::{builder::Ready, GameState};
+use super::{
+    building_model::{Activity, BuildingModel, BuildingModelPlugin, ModelParts, PartMaterial},
+    GameState,
+};
+
+const PULSE_RATE: f32 = 1.5;
 
 pub struct GeneratorPlugin;
 
 impl Plugin for GeneratorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (update, init)
-                .chain()
-                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
-        );
+        app.add_plugins(BuildingModelPlugin::<Generator>::default())
+            .add_systems(
+                Update,
+                update.run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            );
     }
 }
 
+pub const BALL_MATERIAL: &str = "Material.019";
+
 #[derive(Component)]
 pub struct Generator;
 
-#[derive(Component, PartialEq)]
-enum GeneratorState {
-    Materials,
-    Done { ball: Entity },
+impl BuildingModel for Generator {
+    fn model() -> &'static str {
+        "generator.glb"
+    }
+
+    fn parts() -> &'static [&'static str] {
+        &[BALL_MATERIAL]
+    }
 }
 
-fn init(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    rooms: Query<(Entity, Option<&GeneratorState>), With<Generator>>,
-    children: Query<&Children>,
-    gltf_materials: Query<&GltfMaterialName>,
+// The ball pulses harder the more of the generated energy is in use
+fn update(
+    generators: Query<(&ModelParts, &Activity), With<Generator>>,
+    part_materials: Query<&PartMaterial>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
 ) {
-    for (entity, state) in rooms.iter() {
-        match state {
-            None => {
-                commands.entity(entity).insert((
-                    SceneRoot(
-                        asset_server.load(GltfAssetLabel::Scene(0).from_asset("generator.glb")),
-                    ),
-                    GeneratorState::Materials,
-                    Visibility::Hidden,
-                ));
-            }
-            Some(GeneratorState::Materials) => {
-                for child in children.iter_descendants(entity) {
-                    if gltf_materials
-                        .get(child)
-                        .map_or(false, |m| m.0 == "Material.019")
-                    {
-                        commands
-                            .entity(entity)
-                            .insert(Ready)
-                            .insert(GeneratorState::Done { ball: child })
-                            .insert(Visibility::Inherited);
-                    }
-                }
-            }
-            Some(GeneratorState::Done { .. }) => {}
+    let pulse = (time.elapsed_secs() * TAU * PULSE_RATE).sin();
+    for (parts, Activity(activity)) in generators.iter() {
+        for ball in part_materials.iter_many(parts.get(BALL_MATERIAL)) {
+            ball.set_emissive(&mut materials, 0.2 + activity * (1.5 + pulse));
         }
     }
 }
-
-fn update() {}
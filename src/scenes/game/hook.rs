@@ -5,7 +5,11 @@ use bevy_rapier2d::prelude::*;
 use rand_distr::num_traits::Zero;
 
 use crate::{
-    components::{collisions::Collisions, material_modifier::MaterialModifier},
+    components::{
+        collisions::Collisions,
+        material_modifier::MaterialModifier,
+        sfx::{PlaySfx, Sfx},
+    },
     scenes::AppState,
 };
 
@@ -15,7 +19,9 @@ use super::{
     map_state::MapState,
     player::PlayerState,
     rock::{Rock, RockState},
-    GameState,
+    simulation::SimCollider,
+    stats::RockHarvested,
+    GameState, RunStats,
 };
 
 pub struct HookPlugin;
@@ -31,11 +37,24 @@ impl Plugin for HookPlugin {
     }
 }
 
+pub struct HeadlessHookPlugin;
+
+impl Plugin for HeadlessHookPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (init_headless, update).chain());
+    }
+}
+
+pub const HEAD_MODEL: &str = "hook_head.glb";
+pub const BASE_MODEL: &str = "hook_base.glb";
+const HEAD_RADIUS: f32 = 0.33;
+const RADAR_RADIUS: f32 = 9.5;
+
 #[derive(Component)]
 pub struct Hook(pub bool);
 
 #[derive(Component)]
-enum HookState {
+pub enum HookState {
     Idle,
     Flying {
         dir: Dir2,
@@ -77,9 +96,9 @@ fn init(
                 let head = commands
                     .spawn((
                         SceneRoot(
-                            asset_server.load(GltfAssetLabel::Scene(0).from_asset("hook_head.glb")),
+                            asset_server.load(GltfAssetLabel::Scene(0).from_asset(HEAD_MODEL)),
                         ),
-                        Collider::ball(0.33),
+                        Collider::ball(HEAD_RADIUS),
                         ActiveEvents::COLLISION_EVENTS,
                         ActiveCollisionTypes::STATIC_STATIC,
                     ))
@@ -87,7 +106,7 @@ fn init(
                 let radar = commands
                     .spawn((
                         Transform::default(),
-                        Collider::ball(9.5),
+                        Collider::ball(RADAR_RADIUS),
                         ActiveEvents::COLLISION_EVENTS,
                         ActiveCollisionTypes::STATIC_STATIC,
                     ))
@@ -96,12 +115,12 @@ fn init(
                     .entity(entity)
                     .insert((
                         SceneRoot(
-                            asset_server.load(GltfAssetLabel::Scene(0).from_asset("hook_base.glb")),
+                            asset_server.load(GltfAssetLabel::Scene(0).from_asset(BASE_MODEL)),
                         ),
                         Ready,
                         LoadingState::Done { body, head, radar },
-                        HookState::Idle,
                     ))
+                    .insert_if_new(HookState::Idle)
                     .add_children(&[body, head, radar]);
             }
             _ => {}
@@ -109,8 +128,31 @@ fn init(
     }
 }
 
+fn init_headless(
+    mut commands: Commands,
+    hooks: Query<Entity, (With<Hook>, Without<LoadingState>)>,
+) {
+    for entity in hooks.iter() {
+        let body = commands
+            .spawn((Transform::default(), Visibility::Hidden))
+            .id();
+        let head = commands
+            .spawn((Transform::default(), SimCollider(HEAD_RADIUS)))
+            .id();
+        let radar = commands
+            .spawn((Transform::default(), SimCollider(RADAR_RADIUS)))
+            .id();
+        commands
+            .entity(entity)
+            .insert(LoadingState::Done { body, head, radar })
+            .insert_if_new(HookState::Idle)
+            .add_children(&[body, head, radar]);
+    }
+}
+
 fn user_interact(
     mut commands: Commands,
+    map_state: Res<MapState>,
     player_state: Res<State<PlayerState>>,
     game_cursor: Option<Res<GameCursor>>,
     mut hooks: Query<
@@ -126,6 +168,10 @@ fn user_interact(
         return;
     };
 
+    if !map_state.is_enabled(px, py) {
+        return;
+    }
+
     for (entity, loading_state, mut hook_state, transform) in hooks.iter_mut() {
         match loading_state {
             LoadingState::Done { .. } => {}
@@ -157,6 +203,7 @@ fn user_interact(
                     return;
                 };
                 *hook_state = HookState::Flying { dir, length: 0.0 };
+                commands.trigger(PlaySfx(Sfx::HookLaunch));
             }
             _ => {}
         }
@@ -171,6 +218,7 @@ fn update(
     mut transforms: Query<&mut Transform, Without<RockState>>,
     mut targeted_rocks: Local<HashSet<Entity>>,
     mut map_state: ResMut<MapState>,
+    mut run_stats: ResMut<RunStats>,
     collisions: Res<Collisions>,
     time: Res<Time>,
 ) {
@@ -224,6 +272,8 @@ fn update(
                             for (cargo, count) in rock.resources() {
                                 map_state.harvest(cargo, count);
                             }
+                            run_stats.rocks_harvested += 1;
+                            commands.trigger(RockHarvested(rock.kind()));
                         }
                         if let Some(rock) = commands.get_entity(rock) {
                             targeted_rocks.remove(&rock.id());
@@ -267,11 +317,18 @@ fn update(
                             rock: Some(*rock),
                         };
                         *rock_state = RockState::Hooked;
+                        commands.trigger(PlaySfx(Sfx::HookCatch));
                         break;
                     }
                 }
             }
             HookState::Idle if *automatic => {
+                // Unpowered hook rooms stop hunting for rocks
+                let IVec2 { x, y } =
+                    GameCursor::world_to_game(origin.x, origin.y, CursorLayer::Room);
+                if map_state.power(x, y) <= 0.0 {
+                    continue;
+                }
                 let mut target: Option<(Entity, Vec2)> = None;
                 for entity in collisions.get(radar) {
                     if targeted_rocks.contains(entity) {
@@ -308,7 +365,8 @@ fn update(
                 if let Some((entity, pos)) = target {
                     if let Ok(dir) = Dir2::new(pos - origin) {
                         targeted_rocks.insert(entity);
-                        *hook_state = HookState::Flying { dir, length: 0.0 }
+                        *hook_state = HookState::Flying { dir, length: 0.0 };
+                        commands.trigger(PlaySfx(Sfx::HookLaunch));
                     }
                 }
             }
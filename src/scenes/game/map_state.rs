@@ -1,30 +1,42 @@
 use core::f32;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use bevy::{
     prelude::*,
     utils::{HashMap, HashSet},
 };
-use rand_distr::num_traits::Zero;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use crate::scenes::AppState;
 
-use super::GameState;
+use super::{
+    buildings::{BuildingDef, Buildings, Process},
+    GameState,
+};
+
+const BUFFER_SIZE: f32 = 5.0;
+const TRANSFER_SPEED: f32 = 1.0;
+const SHIELD_CAPACITY: f32 = 100.0;
+const ROOM_HP: f32 = 100.0;
+const REPAIR_SILICON_PER_HP: f32 = 0.2;
 
 pub struct MapStatePlugin;
 
 impl Plugin for MapStatePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(MapState::default()).add_systems(
-            PreUpdate,
-            (tick, check_connectivity)
-                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
-        );
+        app.insert_resource(MapState::default())
+            .insert_resource(LogisticsMode::default())
+            .add_systems(
+                PreUpdate,
+                (tick, check_connectivity)
+                    .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            );
     }
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MapNode {
     PrimaryBlock,
     EmptyRoom,
@@ -34,9 +46,11 @@ pub enum MapNode {
     Cargo,
     Hook,
     Enrichment,
+    Shield,
+    Custom(String),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, Serialize, Deserialize)]
 pub enum Cargo {
     Stone,
     Silicon,
@@ -50,6 +64,32 @@ pub enum Cargo {
     Batteries,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, EnumIter, Serialize, Deserialize)]
+pub enum PowerPriority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl PowerPriority {
+    pub fn name(&self) -> &str {
+        match self {
+            PowerPriority::High => "High",
+            PowerPriority::Normal => "Normal",
+            PowerPriority::Low => "Low",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            PowerPriority::High => PowerPriority::Normal,
+            PowerPriority::Normal => PowerPriority::Low,
+            PowerPriority::Low => PowerPriority::High,
+        }
+    }
+}
+
 impl Cargo {
     pub fn name(&self) -> &str {
         match self {
@@ -67,62 +107,48 @@ impl Cargo {
     }
 }
 
-impl MapNode {
-    pub fn thumbnail(&self) -> &str {
-        match self {
-            MapNode::PrimaryBlock => "primary.png",
-            MapNode::EmptyRoom => "room.png",
-            MapNode::Furnace => "furnace.png",
-            MapNode::Generator => "generator.png",
-            MapNode::Crusher => "crusher.png",
-            MapNode::Cargo => "cargo.png",
-            MapNode::Hook => "hook.png",
-            MapNode::Enrichment => "enrichment.png",
-        }
-    }
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProcessStatus {
+    Running,
+    Starved(Cargo),
+    OutputFull,
+}
 
-    pub fn name(&self) -> &str {
-        match self {
-            MapNode::PrimaryBlock => "Main block",
-            MapNode::EmptyRoom => "Empty room",
-            MapNode::Furnace => "Furnace",
-            MapNode::Generator => "Generator",
-            MapNode::Crusher => "Crusher",
-            MapNode::Cargo => "Cargo",
-            MapNode::Hook => "Hook",
-            MapNode::Enrichment => "Enrichment station",
-        }
-    }
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LogisticsMode(pub bool);
 
-    pub fn recipe(&self) -> BTreeMap<Cargo, f32> {
-        match self {
-            MapNode::PrimaryBlock => vec![],
-            MapNode::EmptyRoom => vec![(Cargo::Silicon, 10.0)],
-            MapNode::Furnace => vec![(Cargo::Silicon, 10.0), (Cargo::Ice, 5.0)],
-            MapNode::Generator => vec![(Cargo::Silicon, 20.0), (Cargo::UraniumRods, 2.0)],
-            MapNode::Crusher => vec![(Cargo::Silicon, 30.0), (Cargo::CopperPlates, 10.0)],
-            MapNode::Cargo => vec![(Cargo::Silicon, 10.0), (Cargo::CopperPlates, 5.0)],
-            MapNode::Hook => vec![(Cargo::Silicon, 50.0), (Cargo::Water, 20.0)],
-            MapNode::Enrichment => vec![(Cargo::Silicon, 100.0), (Cargo::UraniumRods, 30.0)],
-        }
-        .into_iter()
-        .collect()
-    }
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    pub input: BTreeMap<Cargo, f32>,
+    pub output: BTreeMap<Cargo, f32>,
+}
 
-    pub fn desc(&self) -> &str {
-        match self {
-            MapNode::PrimaryBlock => "Your rescue capsule",
-            MapNode::EmptyRoom => "Just an empty room",
-            MapNode::Furnace => "Melts ores and ice",
-            MapNode::Generator => "Generates power",
-            MapNode::Crusher => "Crushes stones into the silicone dust",
-            MapNode::Cargo => "Increases your storage capabilities",
-            MapNode::Hook => "Automatic hook",
-            MapNode::Enrichment => "Produces batteries",
+impl Inventory {
+    fn process(&mut self, process: &Process, delta: f32) -> f32 {
+        let from_min = process
+            .from
+            .iter()
+            .map(|f| self.input.get(f).cloned().unwrap_or_default())
+            .reduce(f32::min)
+            .unwrap_or_default();
+        let to_space = BUFFER_SIZE - self.output.get(&process.to).cloned().unwrap_or_default();
+
+        let (from_sub, to_add) = process_amounts(process, from_min, to_space, delta);
+
+        for from in &process.from {
+            *self.input.entry(from.clone()).or_default() -= from_sub;
         }
+        *self.output.entry(process.to.clone()).or_default() += to_add;
+        from_sub
     }
 }
 
+fn process_amounts(process: &Process, from_min: f32, to_space: f32, delta: f32) -> (f32, f32) {
+    let from_sub = (process.speed * delta).min(from_min);
+    let to_add = (from_sub * process.ratio).min(to_space);
+    (to_add / process.ratio, to_add)
+}
+
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub enum MapLayer {
     Main,
@@ -138,6 +164,20 @@ pub struct MapState {
     energy_in_use: f32,
     cargo: HashMap<Cargo, f32>,
     cargo_max: HashMap<Cargo, f32>,
+    priorities: HashMap<IVec2, PowerPriority>,
+    power: HashMap<IVec2, f32>,
+    logistics: bool,
+    inventories: HashMap<IVec2, Inventory>,
+    distances: HashMap<IVec2, u32>,
+    // Other rooms reachable from each production room, nearest first
+    routes: HashMap<IVec2, Vec<(IVec2, u32)>>,
+    routes_valid: bool,
+    shields: HashMap<IVec2, f32>,
+    damage: HashMap<IVec2, f32>,
+    activity: HashMap<IVec2, f32>,
+    rates: HashMap<IVec2, Vec<f32>>,
+    disabled: HashSet<IVec2>,
+    flow: BTreeMap<Cargo, (f32, f32)>,
 }
 
 impl MapState {
@@ -153,6 +193,9 @@ impl MapState {
     }
 
     fn add(&mut self, x: i32, y: i32, node: MapNode, layer: MapLayer) {
+        if layer == MapLayer::Main {
+            self.routes_valid = false;
+        }
         self.map_by_layer
             .entry(layer)
             .or_default()
@@ -161,6 +204,10 @@ impl MapState {
     }
 
     fn remove(&mut self, x: i32, y: i32, layer: MapLayer) {
+        if layer == MapLayer::Main {
+            self.damage.remove(&IVec2::new(x, y));
+            self.routes_valid = false;
+        }
         self.map_by_layer
             .entry(layer)
             .or_default()
@@ -173,6 +220,9 @@ impl MapState {
     }
 
     pub fn add_room(&mut self, x: i32, y: i32, node: MapNode) {
+        self.priorities.remove(&IVec2::new(x, y));
+        self.inventories.remove(&IVec2::new(x, y));
+        self.disabled.remove(&IVec2::new(x, y));
         self.add(x, y, node, MapLayer::Main);
     }
 
@@ -257,177 +307,1090 @@ impl MapState {
         )
     }
 
+    pub fn rooms(&self) -> Vec<(IVec2, MapNode)> {
+        let mut rooms = self
+            .map_by_layer
+            .get(&MapLayer::Main)
+            .map(|m| m.iter().map(|(c, n)| (*c, n.clone())).collect::<Vec<_>>())
+            .unwrap_or_default();
+        rooms.sort_by_key(|(c, _)| (c.x, c.y));
+        rooms
+    }
+
+    pub fn cargo(&self) -> Vec<(Cargo, f32)> {
+        self.cargo.iter().map(|(c, n)| (c.clone(), *n)).collect()
+    }
+
+    pub fn priorities(&self) -> Vec<(IVec2, PowerPriority)> {
+        self.priorities.iter().map(|(c, p)| (*c, *p)).collect()
+    }
+
+    pub fn damage(&self) -> Vec<(IVec2, f32)> {
+        self.damage.iter().map(|(c, d)| (*c, *d)).collect()
+    }
+
+    pub fn disabled(&self) -> Vec<IVec2> {
+        self.disabled.iter().cloned().collect()
+    }
+
+    pub fn inventories(&self) -> Vec<(IVec2, Inventory)> {
+        self.inventories
+            .iter()
+            .map(|(c, i)| (*c, i.clone()))
+            .collect()
+    }
+
+    pub fn restore(
+        &mut self,
+        rooms: impl IntoIterator<Item = (IVec2, MapNode)>,
+        cargo: impl IntoIterator<Item = (Cargo, f32)>,
+        priorities: impl IntoIterator<Item = (IVec2, PowerPriority)>,
+        inventories: impl IntoIterator<Item = (IVec2, Inventory)>,
+        damage: impl IntoIterator<Item = (IVec2, f32)>,
+        disabled: impl IntoIterator<Item = IVec2>,
+    ) {
+        let logistics = self.logistics;
+        *self = Self::default();
+        for (IVec2 { x, y }, node) in rooms {
+            self.add(x, y, node, MapLayer::Main);
+        }
+        self.cargo = cargo.into_iter().collect();
+        self.priorities = priorities.into_iter().collect();
+        self.inventories = inventories.into_iter().collect();
+        self.damage = damage.into_iter().collect();
+        self.disabled = disabled.into_iter().collect();
+        self.logistics = logistics;
+    }
+
+    pub fn logistics(&self) -> bool {
+        self.logistics
+    }
+
+    pub fn set_logistics(&mut self, logistics: bool) {
+        self.logistics = logistics;
+    }
+
+    // Which rooms are storage or production depends on the building definitions
+    pub fn invalidate_routes(&mut self) {
+        self.routes_valid = false;
+    }
+
+    pub fn inventory(&self, x: i32, y: i32) -> Option<&Inventory> {
+        self.inventories.get(&IVec2::new(x, y))
+    }
+
+    pub fn distance(&self, x: i32, y: i32) -> Option<u32> {
+        self.distances.get(&IVec2::new(x, y)).cloned()
+    }
+
+    pub fn shield_charge(&self, x: i32, y: i32) -> f32 {
+        self.shields
+            .get(&IVec2::new(x, y))
+            .map_or(0.0, |charge| charge / SHIELD_CAPACITY)
+    }
+
+    pub fn health(&self, x: i32, y: i32) -> f32 {
+        let damage = self
+            .damage
+            .get(&IVec2::new(x, y))
+            .cloned()
+            .unwrap_or_default();
+        1.0 - damage / ROOM_HP
+    }
+
+    pub fn damage_room(&mut self, x: i32, y: i32, amount: f32) {
+        if !self.is_room(x, y, MapLayer::Main) {
+            return;
+        }
+        let damage = self.damage.entry(IVec2::new(x, y)).or_default();
+        *damage += amount;
+        if *damage >= ROOM_HP {
+            self.damage.remove(&IVec2::new(x, y));
+            self.remove_room(x, y, MapLayer::Main);
+        }
+    }
+
+    pub fn repair_cost(&self, x: i32, y: i32) -> f32 {
+        self.damage
+            .get(&IVec2::new(x, y))
+            .cloned()
+            .unwrap_or_default()
+            * REPAIR_SILICON_PER_HP
+    }
+
+    // Repairs as much as the stored silicon allows
+    pub fn repair(&mut self, x: i32, y: i32) {
+        let Some(damage) = self.damage.get_mut(&IVec2::new(x, y)) else {
+            return;
+        };
+        let silicon = self.cargo.entry(Cargo::Silicon).or_default();
+        let repaired = damage.min(*silicon / REPAIR_SILICON_PER_HP);
+        *silicon -= repaired * REPAIR_SILICON_PER_HP;
+        *damage -= repaired;
+        if *damage <= 0.0 {
+            self.damage.remove(&IVec2::new(x, y));
+        }
+        self.record_flow(&Cargo::Silicon, -repaired * REPAIR_SILICON_PER_HP);
+    }
+
+    pub fn absorb_impact(&mut self, x: i32, y: i32, cost: f32) -> bool {
+        match self.shields.get_mut(&IVec2::new(x, y)) {
+            Some(charge) if *charge >= cost => {
+                *charge -= cost;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn priority(&self, x: i32, y: i32, buildings: &Buildings) -> PowerPriority {
+        let pos = IVec2::new(x, y);
+        self.priorities.get(&pos).cloned().unwrap_or_else(|| {
+            self.node(x, y, MapLayer::Main)
+                .and_then(|node| buildings.get(&node))
+                .map(|def| def.priority)
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn set_priority(&mut self, x: i32, y: i32, priority: PowerPriority) {
+        self.priorities.insert(IVec2::new(x, y), priority);
+    }
+
+    pub fn is_enabled(&self, x: i32, y: i32) -> bool {
+        !self.disabled.contains(&IVec2::new(x, y))
+    }
+
+    // Disabled rooms neither use nor produce energy, and stop processing
+    pub fn set_enabled(&mut self, x: i32, y: i32, enabled: bool) {
+        if enabled {
+            self.disabled.remove(&IVec2::new(x, y));
+        } else if self.is_room(x, y, MapLayer::Main) {
+            self.disabled.insert(IVec2::new(x, y));
+        }
+    }
+
+    pub fn power(&self, x: i32, y: i32) -> f32 {
+        self.power.get(&IVec2::new(x, y)).cloned().unwrap_or(1.0)
+    }
+
     pub fn harvest(&mut self, cargo: Cargo, count: f32) {
         let cur = self.cargo.entry(cargo.clone()).or_default();
+        let before = *cur;
         *cur += count;
         *cur = cur.min(self.cargo_max.get(&cargo).cloned().unwrap_or_default());
+        let added = *cur - before;
+        self.record_flow(&cargo, added);
+    }
+
+    // Share of the room's full processing speed used in the last tick, or
+    // the share of the generated energy in use for generators
+    pub fn activity(&self, x: i32, y: i32) -> f32 {
+        self.activity
+            .get(&IVec2::new(x, y))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Output per second of the room's process with the given index in the last tick
+    pub fn output_rate(&self, x: i32, y: i32, process: usize) -> f32 {
+        self.rates
+            .get(&IVec2::new(x, y))
+            .and_then(|rates| rates.get(process))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Whether the process has inputs to consume and space for its output,
+    // in the room buffers with logistics or in the ship storage without
+    pub fn process_status(&self, x: i32, y: i32, process: &Process) -> ProcessStatus {
+        let inventory = self
+            .inventories
+            .get(&IVec2::new(x, y))
+            .filter(|_| self.logistics);
+        let input = |cargo: &Cargo| {
+            match inventory {
+                Some(inventory) => inventory.input.get(cargo),
+                None => self.cargo.get(cargo),
+            }
+            .cloned()
+            .unwrap_or_default()
+        };
+        let (output, max) = match inventory {
+            Some(inventory) => (
+                inventory
+                    .output
+                    .get(&process.to)
+                    .cloned()
+                    .unwrap_or_default(),
+                BUFFER_SIZE,
+            ),
+            None => self.cargo_count(process.to.clone()),
+        };
+
+        if let Some(cargo) = process
+            .from
+            .iter()
+            .find(|cargo| input(cargo) <= f32::EPSILON)
+        {
+            return ProcessStatus::Starved(cargo.clone());
+        }
+        if max - output <= f32::EPSILON {
+            return ProcessStatus::OutputFull;
+        }
+        ProcessStatus::Running
+    }
+
+    // Cargo produced and consumed since the last call
+    pub fn take_flow(&mut self) -> BTreeMap<Cargo, (f32, f32)> {
+        std::mem::take(&mut self.flow)
+    }
+
+    fn record_flow(&mut self, cargo: &Cargo, amount: f32) {
+        let (produced, consumed) = self.flow.entry(cargo.clone()).or_default();
+        if amount > 0.0 {
+            *produced += amount;
+        } else {
+            *consumed -= amount;
+        }
+    }
+
+    pub fn fill_level(&self) -> f32 {
+        let max = self.cargo_max.values().sum::<f32>();
+        if max <= 0.0 {
+            return 0.0;
+        }
+        (self.cargo.values().sum::<f32>() / max).clamp(0.0, 1.0)
+    }
+
+    pub fn energy(&self) -> (f32, f32) {
+        (self.energy_available, self.energy_in_use)
     }
 
     pub fn energy_ratio(&self) -> f32 {
+        if self.energy_available <= 0.0 {
+            return 0.0;
+        }
         ((self.energy_available - self.energy_in_use) / self.energy_available)
             .max(0.0)
             .min(1.0)
     }
-}
 
-fn tick(mut map_state: ResMut<MapState>, time: Res<Time>) {
-    let Some(map) = map_state.map_by_layer.get(&MapLayer::Main).cloned() else {
-        return;
-    };
+    pub fn tick(&mut self, buildings: &Buildings, delta: f32) {
+        let map = self.rooms();
 
-    let mut energy_available = 0.0;
-    let mut energy_in_use = 0.0;
-    let mut cargo_max = HashMap::<Cargo, f32>::new();
-    for node in map.values() {
-        match node {
-            MapNode::PrimaryBlock => {
-                energy_available += 50.0;
-                *cargo_max.entry(Cargo::Silicon).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Ice).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Copper).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Uranium).or_default() += 1.0;
-                *cargo_max.entry(Cargo::CopperPlates).or_default() += 10.0;
+        let mut energy_available = 0.0;
+        let mut energy_in_use = 0.0;
+        let mut cargo_max = HashMap::<Cargo, f32>::new();
+        for (c, def) in map
+            .iter()
+            .filter_map(|(c, node)| Some((c, buildings.get(node)?)))
+        {
+            if !self.disabled.contains(c) {
+                energy_available += def.energy_output;
+                energy_in_use += def.energy_use;
             }
-            MapNode::EmptyRoom => {
-                energy_in_use += 5.0;
+            for (cargo, count) in &def.storage {
+                *cargo_max.entry(cargo.clone()).or_default() += count;
+            }
+        }
+
+        for (cargo, count) in &mut self.cargo {
+            *count = count.min(cargo_max.get(cargo).cloned().unwrap_or_default());
+        }
+
+        self.cargo_max = cargo_max;
+        self.energy_available = energy_available;
+        self.energy_in_use = energy_in_use;
+
+        let live = map.iter().map(|(c, _)| *c).collect::<HashSet<_>>();
+        self.priorities.retain(|c, _| live.contains(c));
+        self.disabled.retain(|c| live.contains(c));
+
+        let mut power = HashMap::new();
+        for c in &self.disabled {
+            power.insert(*c, 0.0);
+        }
+        let mut energy_left = energy_available;
+        for priority in PowerPriority::iter() {
+            let tier = map
+                .iter()
+                .filter(|(c, _)| !self.disabled.contains(c))
+                .filter(|(c, _)| self.priority(c.x, c.y, buildings) == priority)
+                .filter_map(|(c, node)| Some((*c, buildings.get(node)?.energy_use)))
+                .collect::<Vec<_>>();
+            let demand = tier.iter().map(|(_, energy_use)| energy_use).sum::<f32>();
+            let ratio = if demand > 0.0 {
+                (energy_left / demand).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            energy_left = (energy_left - demand).max(0.0);
+            for (c, energy_use) in tier {
+                power.insert(c, if energy_use > 0.0 { ratio } else { 1.0 });
             }
-            MapNode::Furnace => {
-                energy_in_use += 5.0;
-                energy_in_use += 20.0;
+        }
+        self.power = power;
+
+        self.shields.retain(|c, _| {
+            map.iter()
+                .any(|(pos, node)| pos == c && *node == MapNode::Shield)
+        });
+        for (c, node) in &map {
+            if *node != MapNode::Shield {
+                continue;
             }
-            MapNode::Generator => {
-                energy_in_use += 5.0;
-                energy_available += 100.0;
+            let recharge = buildings.get(node).map_or(0.0, |def| def.energy_use);
+            let charge = self.shields.entry(*c).or_default();
+            *charge = (*charge + recharge * self.power.get(c).cloned().unwrap_or(1.0) * delta)
+                .min(SHIELD_CAPACITY);
+        }
+
+        if self.logistics {
+            self.inventories
+                .retain(|c, _| map.iter().any(|(pos, _)| pos == c));
+            if !self.routes_valid {
+                self.distances = self.storage_distances(buildings);
+                self.routes = self.production_routes(buildings);
+                self.routes_valid = true;
             }
-            MapNode::Crusher => {
-                energy_in_use += 5.0;
-                energy_in_use += 10.0;
+        }
+
+        let load = if energy_available > 0.0 {
+            (energy_in_use / energy_available).min(1.0)
+        } else {
+            0.0
+        };
+
+        let mut activity = HashMap::new();
+        let mut rates = HashMap::new();
+        for (c, node) in &map {
+            let Some(def) = buildings.get(node) else {
+                continue;
+            };
+            if self.disabled.contains(c) {
+                continue;
             }
-            MapNode::Cargo => {
-                energy_in_use += 5.0;
-                *cargo_max.entry(Cargo::Stone).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Silicon).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Ice).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Copper).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Uranium).or_default() += 10.0;
-                *cargo_max.entry(Cargo::Aurelium).or_default() += 1.0;
-                *cargo_max.entry(Cargo::Water).or_default() += 10.0;
-                *cargo_max.entry(Cargo::CopperPlates).or_default() += 10.0;
-                *cargo_max.entry(Cargo::UraniumRods).or_default() += 5.0;
-                *cargo_max.entry(Cargo::Batteries).or_default() += 1.0;
+            let power = self.power(c.x, c.y);
+            let mut consumed = vec![];
+            if self.logistics && !def.processes.is_empty() {
+                self.transfer(*c, def, delta);
+                let inventory = self.inventories.entry(*c).or_default();
+                for process in &def.processes {
+                    consumed.push(inventory.process(process, delta * power));
+                }
+            } else {
+                for process in &def.processes {
+                    consumed.push(self.process(process, delta * power));
+                }
             }
-            MapNode::Hook => {
-                energy_in_use += 5.0;
-                energy_in_use += 50.0;
+
+            for (process, consumed) in def.processes.iter().zip(&consumed) {
+                for from in &process.from {
+                    self.record_flow(from, -consumed);
+                }
+                self.record_flow(&process.to, consumed * process.ratio);
             }
-            MapNode::Enrichment => {
-                energy_in_use += 5.0;
-                energy_in_use += 200.0;
+
+            if !def.processes.is_empty() && delta > 0.0 {
+                rates.insert(
+                    *c,
+                    def.processes
+                        .iter()
+                        .zip(&consumed)
+                        .map(|(process, consumed)| consumed * process.ratio / delta)
+                        .collect::<Vec<_>>(),
+                );
+                let rate = def
+                    .processes
+                    .iter()
+                    .zip(consumed)
+                    .map(|(process, consumed)| consumed / (process.speed * delta))
+                    .sum::<f32>()
+                    / def.processes.len() as f32;
+                activity.insert(*c, rate.clamp(0.0, 1.0));
+            } else if def.energy_output > 0.0 {
+                activity.insert(*c, load);
             }
         }
+        self.activity = activity;
+        self.rates = rates;
     }
 
-    for (cargo, count) in &mut map_state.cargo {
-        *count = count.min(cargo_max.get(cargo).cloned().unwrap_or_default());
+    fn storage_distances(&self, buildings: &Buildings) -> HashMap<IVec2, u32> {
+        let Some(map) = self.map_by_layer.get(&MapLayer::Main) else {
+            return HashMap::new();
+        };
+
+        grid_distances(
+            map,
+            map.iter()
+                .filter(|(_, node)| {
+                    buildings
+                        .get(node)
+                        .is_some_and(|def| !def.storage.is_empty())
+                })
+                .map(|(c, _)| *c),
+        )
     }
 
-    map_state.cargo_max = cargo_max;
-    map_state.energy_available = energy_available;
-    map_state.energy_in_use = energy_in_use;
+    fn production_routes(&self, buildings: &Buildings) -> HashMap<IVec2, Vec<(IVec2, u32)>> {
+        let Some(map) = self.map_by_layer.get(&MapLayer::Main) else {
+            return HashMap::new();
+        };
 
-    if map_state.energy_ratio().is_zero() {
-        return;
+        map.iter()
+            .filter(|(_, node)| {
+                buildings
+                    .get(node)
+                    .is_some_and(|def| !def.processes.is_empty())
+            })
+            .map(|(c, _)| {
+                let mut route = grid_distances(map, [*c])
+                    .into_iter()
+                    .filter(|(other, _)| other != c)
+                    .collect::<Vec<_>>();
+                route.sort_by_key(|(other, distance)| (*distance, other.x, other.y));
+                (*c, route)
+            })
+            .collect()
     }
 
-    let delta = time.delta_secs();
+    // Storage rooms share the ship storage, other rooms offer their output buffer
+    fn available(&self, pos: IVec2, cargo: &Cargo) -> f32 {
+        if self.distances.get(&pos) == Some(&0) {
+            self.cargo.get(cargo)
+        } else {
+            self.inventories
+                .get(&pos)
+                .and_then(|inventory| inventory.output.get(cargo))
+        }
+        .cloned()
+        .unwrap_or_default()
+    }
 
-    let mut process = |(from, to, speed, ratio): (Vec<Cargo>, Cargo, f32, f32)| {
-        let to_max = map_state.cargo_max.get(&to).cloned().unwrap_or_default();
-        let to_cur = map_state.cargo.get(&to).cloned().unwrap_or_default();
-        let from_cur = from
+    // Pulls inputs from the nearest room holding them and pushes outputs to the
+    // ship storage, slower the longer the path through the rooms in between
+    fn transfer(&mut self, pos: IVec2, def: &BuildingDef, delta: f32) {
+        let Some(distance) = self.distances.get(&pos).cloned().filter(|d| *d > 0) else {
+            return;
+        };
+
+        let inputs = def
+            .processes
             .iter()
-            .map(|f| map_state.cargo.get(f).cloned().unwrap_or_default())
-            .collect::<Vec<_>>();
+            .flat_map(|process| &process.from)
+            .collect::<BTreeSet<_>>();
+        for cargo in inputs {
+            let Some((source, distance)) = self.routes.get(&pos).and_then(|route| {
+                route
+                    .iter()
+                    .find(|(other, _)| self.available(*other, cargo) > 0.0)
+                    .cloned()
+            }) else {
+                continue;
+            };
+            let buffered = self
+                .inventories
+                .get(&pos)
+                .and_then(|inventory| inventory.input.get(cargo))
+                .cloned()
+                .unwrap_or_default();
+            let moved = (TRANSFER_SPEED * delta / distance as f32)
+                .min(self.available(source, cargo))
+                .min(BUFFER_SIZE - buffered)
+                .max(0.0);
 
-        let mut from_min = f32::MAX;
-        for value in &from_cur {
-            if *value < from_min {
-                from_min = *value;
-            }
+            let from = if self.distances.get(&source) == Some(&0) {
+                self.cargo.entry(cargo.clone()).or_default()
+            } else {
+                self.inventories
+                    .entry(source)
+                    .or_default()
+                    .output
+                    .entry(cargo.clone())
+                    .or_default()
+            };
+            *from -= moved;
+            *self
+                .inventories
+                .entry(pos)
+                .or_default()
+                .input
+                .entry(cargo.clone())
+                .or_default() += moved;
         }
-        if from_min == f32::MAX {
-            from_min = 0.0;
+
+        let amount = TRANSFER_SPEED * delta / distance as f32;
+        let inventory = self.inventories.entry(pos).or_default();
+        for (cargo, buffered) in &mut inventory.output {
+            let max = self.cargo_max.get(cargo).cloned().unwrap_or_default();
+            let stored = self.cargo.entry(cargo.clone()).or_default();
+            let moved = amount.min(*buffered).min(max - *stored).max(0.0);
+            *stored += moved;
+            *buffered -= moved;
         }
+    }
+
+    pub fn process(&mut self, process: &Process, delta: f32) -> f32 {
+        let Process { from, to, .. } = process;
+        let to_max = self.cargo_max.get(to).cloned().unwrap_or_default();
+        let to_cur = self.cargo.get(to).cloned().unwrap_or_default();
+        let from_min = from
+            .iter()
+            .map(|f| self.cargo.get(f).cloned().unwrap_or_default())
+            .reduce(f32::min)
+            .unwrap_or_default();
 
-        let from_sub = (speed * delta).min(from_min);
-        let to_add = (from_sub * ratio).min(to_max - to_cur);
-        let from_sub = to_add / ratio;
+        let (from_sub, to_add) = process_amounts(process, from_min, to_max - to_cur, delta);
 
-        for from in &from {
-            *map_state.cargo.entry(from.clone()).or_default() -= from_sub;
+        for from in from {
+            *self.cargo.entry(from.clone()).or_default() -= from_sub;
         }
-        *map_state.cargo.entry(to).or_default() += to_add;
-    };
+        *self.cargo.entry(to.clone()).or_default() += to_add;
+        from_sub
+    }
 
-    for node in map.values() {
-        match node {
-            MapNode::Furnace => {
-                [
-                    (vec![Cargo::Ice], Cargo::Water, 0.4, 1.0),
-                    (vec![Cargo::Copper], Cargo::CopperPlates, 0.2, 0.5),
-                    (vec![Cargo::Uranium], Cargo::UraniumRods, 0.1, 0.2),
-                ]
-                .into_iter()
-                .for_each(&mut process);
+    pub fn check_connectivity(&mut self) {
+        for (layer, map) in &mut self.map_by_layer {
+            let mut new_map = map
+                .iter()
+                .filter_map(|(c, n)| match n {
+                    MapNode::PrimaryBlock => Some((*c, MapNode::PrimaryBlock)),
+                    _ => None,
+                })
+                .collect::<HashMap<_, _>>();
+
+            let mut stack = new_map.keys().cloned().collect::<Vec<_>>();
+
+            let mut visited = HashSet::new();
+
+            while let Some(IVec2 { x, y }) = stack.pop() {
+                if !visited.insert(IVec2::new(x, y)) {
+                    continue;
+                }
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let x = x + dx;
+                    let y = y + dy;
+                    if let Some(node) = map.get(&IVec2::new(x, y)) {
+                        stack.push(IVec2::new(x, y));
+                        new_map.insert(IVec2::new(x, y), node.clone());
+                    }
+                }
             }
-            MapNode::Crusher => {
-                [(vec![Cargo::Stone], Cargo::Silicon, 0.5, 1.0)]
-                    .into_iter()
-                    .for_each(&mut process);
+
+            if new_map.len() != map.len() && *layer == MapLayer::Main {
+                self.routes_valid = false;
             }
-            MapNode::Enrichment => {
-                [(
-                    vec![Cargo::UraniumRods, Cargo::Aurelium],
-                    Cargo::Batteries,
-                    0.1,
-                    0.2,
-                )]
-                .into_iter()
-                .for_each(&mut process);
+            *map = new_map;
+        }
+        // Rooms cut off by the cascade take their damage with them
+        if let Some(map) = self.map_by_layer.get(&MapLayer::Main) {
+            self.damage.retain(|c, _| map.contains_key(c));
+        }
+        self.recalculate_bounds();
+    }
+}
+
+// Path lengths through the grid from the nearest start
+fn grid_distances(
+    map: &HashMap<IVec2, MapNode>,
+    starts: impl IntoIterator<Item = IVec2>,
+) -> HashMap<IVec2, u32> {
+    let mut distances = starts
+        .into_iter()
+        .map(|c| (c, 0))
+        .collect::<HashMap<_, _>>();
+
+    let mut queue = distances.keys().cloned().collect::<VecDeque<_>>();
+    while let Some(c) = queue.pop_front() {
+        let distance = distances[&c] + 1;
+        for dir in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+            let next = c + dir;
+            if map.contains_key(&next) && !distances.contains_key(&next) {
+                distances.insert(next, distance);
+                queue.push_back(next);
             }
-            _ => {}
         }
     }
+
+    distances
+}
+
+fn tick(mut map_state: ResMut<MapState>, buildings: Res<Buildings>, time: Res<Time>) {
+    map_state.tick(&buildings, time.delta_secs());
 }
 
 fn check_connectivity(mut map_state: ResMut<MapState>) {
-    for (_, map) in &mut map_state.map_by_layer {
-        let mut new_map = map
-            .iter()
-            .filter_map(|(c, n)| match n {
-                MapNode::PrimaryBlock => Some((*c, MapNode::PrimaryBlock)),
-                _ => None,
-            })
-            .collect::<HashMap<_, _>>();
+    map_state.check_connectivity();
+}
 
-        let mut stack = new_map.keys().cloned().collect::<Vec<_>>();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenes::game::buildings::BuildingDefs;
 
-        let mut visited = HashSet::new();
+    fn buildings() -> Buildings {
+        let defs: BuildingDefs =
+            ron::from_str(include_str!("../../../assets/default.buildings.ron")).unwrap();
+        Buildings::from_defs(&defs)
+    }
 
-        while let Some(IVec2 { x, y }) = stack.pop() {
-            if !visited.insert(IVec2::new(x, y)) {
-                continue;
-            }
-            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
-                let x = x + dx;
-                let y = y + dy;
-                if let Some(node) = map.get(&IVec2::new(x, y)) {
-                    stack.push(IVec2::new(x, y));
-                    new_map.insert(IVec2::new(x, y), node.clone());
-                }
-            }
+    fn map(rooms: &[(i32, i32, MapNode)]) -> MapState {
+        let mut map_state = MapState::default();
+        map_state.add_primary_block(0, 0);
+        for (x, y, node) in rooms {
+            map_state.add_room(*x, *y, node.clone());
         }
+        map_state
+    }
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn empty_room_needs_orthogonal_neighbour() {
+        let map_state = map(&[]);
+
+        assert!(map_state.is_available(1, 0, MapNode::EmptyRoom));
+        assert!(map_state.is_available(0, -1, MapNode::EmptyRoom));
+        assert!(!map_state.is_available(1, 1, MapNode::EmptyRoom));
+        assert!(!map_state.is_available(2, 0, MapNode::EmptyRoom));
+        assert!(!map_state.is_available(0, 0, MapNode::EmptyRoom));
+    }
+
+    #[test]
+    fn rooms_are_built_on_empty_rooms_only() {
+        let map_state = map(&[(1, 0, MapNode::EmptyRoom), (0, 1, MapNode::Cargo)]);
+
+        assert!(map_state.is_available(1, 0, MapNode::Furnace));
+        assert!(!map_state.is_available(0, 1, MapNode::Furnace));
+        assert!(!map_state.is_available(0, 0, MapNode::Furnace));
+        assert!(!map_state.is_available(-1, 0, MapNode::Furnace));
+    }
+
+    #[test]
+    fn nothing_is_available_without_a_map() {
+        let map_state = MapState::default();
+
+        assert!(!map_state.is_available(0, 0, MapNode::EmptyRoom));
+        assert!(!map_state.is_available(0, 0, MapNode::Furnace));
+    }
+
+    #[test]
+    fn removing_a_bridge_destroys_everything_behind_it() {
+        let mut map_state = map(&[
+            (1, 0, MapNode::EmptyRoom),
+            (2, 0, MapNode::Furnace),
+            (3, 0, MapNode::EmptyRoom),
+            (3, 1, MapNode::Cargo),
+            (-1, 0, MapNode::EmptyRoom),
+        ]);
+
+        map_state.remove_room(1, 0, MapLayer::Main);
+        map_state.check_connectivity();
+
+        assert!(!map_state.is_node(2, 0, MapLayer::Main));
+        assert!(!map_state.is_node(3, 0, MapLayer::Main));
+        assert!(!map_state.is_node(3, 1, MapLayer::Main));
+        assert!(map_state.is_node(-1, 0, MapLayer::Main));
+        assert!(map_state.is_node(0, 0, MapLayer::Main));
+        assert_eq!(
+            map_state.get_bounds(),
+            (IVec2::new(-1, 0), IVec2::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn rooms_with_another_path_survive() {
+        let mut map_state = map(&[
+            (1, 0, MapNode::EmptyRoom),
+            (0, 1, MapNode::EmptyRoom),
+            (1, 1, MapNode::EmptyRoom),
+            (2, 1, MapNode::EmptyRoom),
+        ]);
+
+        map_state.remove_room(1, 0, MapLayer::Main);
+        map_state.check_connectivity();
+
+        assert!(map_state.is_node(1, 1, MapLayer::Main));
+        assert!(map_state.is_node(2, 1, MapLayer::Main));
+    }
+
+    #[test]
+    fn harvest_is_clamped_to_storage() {
+        let mut map_state = map(&[]);
+        map_state.tick(&buildings(), 0.0);
+
+        map_state.harvest(Cargo::Silicon, 4.0);
+        assert_eq!(map_state.cargo_count(Cargo::Silicon), (4.0, 10.0));
+
+        map_state.harvest(Cargo::Silicon, 15.0);
+        assert_eq!(map_state.cargo_count(Cargo::Silicon), (10.0, 10.0));
+
+        map_state.harvest(Cargo::Silicon, -3.0);
+        assert_eq!(map_state.cargo_count(Cargo::Silicon), (7.0, 10.0));
+
+        map_state.harvest(Cargo::Stone, 1.0);
+        assert_eq!(map_state.cargo_count(Cargo::Stone), (0.0, 0.0));
+    }
+
+    #[test]
+    fn losing_storage_clamps_cargo() {
+        let mut map_state = map(&[(1, 0, MapNode::Cargo)]);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::Silicon, 20.0);
+        assert_eq!(map_state.cargo_count(Cargo::Silicon), (20.0, 20.0));
+
+        map_state.remove_room(1, 0, MapLayer::Main);
+        map_state.tick(&buildings, 0.0);
+        assert_eq!(map_state.cargo_count(Cargo::Silicon), (10.0, 10.0));
+    }
+
+    #[test]
+    fn process_consumes_every_input() {
+        let mut map_state = MapState::default();
+        map_state.cargo_max = [
+            (Cargo::UraniumRods, 5.0),
+            (Cargo::Aurelium, 1.0),
+            (Cargo::Batteries, 1.0),
+        ]
+        .into_iter()
+        .collect();
+        map_state.harvest(Cargo::UraniumRods, 5.0);
+        map_state.harvest(Cargo::Aurelium, 1.0);
+
+        let enrichment = Process {
+            from: vec![Cargo::UraniumRods, Cargo::Aurelium],
+            to: Cargo::Batteries,
+            speed: 0.1,
+            ratio: 0.2,
+        };
+        map_state.process(&enrichment, 1.0);
+
+        assert_near(map_state.cargo_count(Cargo::UraniumRods).0, 4.9);
+        assert_near(map_state.cargo_count(Cargo::Aurelium).0, 0.9);
+        assert_near(map_state.cargo_count(Cargo::Batteries).0, 0.02);
+    }
+
+    #[test]
+    fn process_is_limited_by_scarcest_input_and_output_space() {
+        let mut map_state = MapState::default();
+        map_state.cargo_max = [
+            (Cargo::UraniumRods, 5.0),
+            (Cargo::Aurelium, 1.0),
+            (Cargo::Batteries, 1.0),
+        ]
+        .into_iter()
+        .collect();
+        map_state.harvest(Cargo::UraniumRods, 5.0);
+        map_state.harvest(Cargo::Aurelium, 0.05);
+
+        let enrichment = Process {
+            from: vec![Cargo::UraniumRods, Cargo::Aurelium],
+            to: Cargo::Batteries,
+            speed: 0.1,
+            ratio: 0.2,
+        };
+        map_state.process(&enrichment, 1.0);
+
+        assert_near(map_state.cargo_count(Cargo::UraniumRods).0, 4.95);
+        assert_near(map_state.cargo_count(Cargo::Aurelium).0, 0.0);
+        assert_near(map_state.cargo_count(Cargo::Batteries).0, 0.01);
+
+        map_state.harvest(Cargo::Aurelium, 1.0);
+        map_state.harvest(Cargo::Batteries, 0.995 - 0.01);
+        map_state.process(&enrichment, 1.0);
+
+        assert_near(map_state.cargo_count(Cargo::Batteries).0, 1.0);
+        assert_near(map_state.cargo_count(Cargo::Aurelium).0, 0.975);
+    }
+
+    #[test]
+    fn tick_runs_enrichment() {
+        let mut map_state = map(&[
+            (1, 0, MapNode::Generator),
+            (2, 0, MapNode::Generator),
+            (0, 1, MapNode::Cargo),
+            (-1, 0, MapNode::Enrichment),
+        ]);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::UraniumRods, 5.0);
+        map_state.harvest(Cargo::Aurelium, 1.0);
+
+        map_state.tick(&buildings, 1.0);
+
+        assert_eq!(map_state.energy(), (250.0, 220.0));
+        assert_near(map_state.cargo_count(Cargo::UraniumRods).0, 4.9);
+        assert_near(map_state.cargo_count(Cargo::Aurelium).0, 0.9);
+        assert_near(map_state.cargo_count(Cargo::Batteries).0, 0.02);
+    }
+
+    #[test]
+    fn low_priority_rooms_are_throttled() {
+        let mut map_state = map(&[(0, 1, MapNode::Cargo), (-1, 0, MapNode::Enrichment)]);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::UraniumRods, 5.0);
+        map_state.harvest(Cargo::Aurelium, 1.0);
+
+        map_state.tick(&buildings, 1.0);
+
+        let power = 45.0 / 205.0;
+        assert_eq!(map_state.energy_ratio(), 0.0);
+        assert_eq!(map_state.power(0, 1), 1.0);
+        assert_near(map_state.power(-1, 0), power);
+        assert_near(map_state.cargo_count(Cargo::Batteries).0, 0.02 * power);
+        assert_near(
+            map_state.cargo_count(Cargo::UraniumRods).0,
+            5.0 - 0.1 * power,
+        );
+    }
+
+    #[test]
+    fn energy_is_allocated_by_priority() {
+        let mut map_state = map(&[
+            (1, 0, MapNode::Furnace),
+            (0, 1, MapNode::Cargo),
+            (-1, 0, MapNode::Enrichment),
+        ]);
+        let buildings = buildings();
+
+        map_state.tick(&buildings, 0.0);
+
+        assert_eq!(map_state.power(0, 0), 1.0);
+        assert_eq!(map_state.power(1, 0), 1.0);
+        assert_eq!(map_state.power(0, 1), 1.0);
+        assert_near(map_state.power(-1, 0), 20.0 / 205.0);
+
+        map_state.set_priority(-1, 0, PowerPriority::High);
+        map_state.tick(&buildings, 0.0);
+
+        assert_near(map_state.power(-1, 0), 50.0 / 230.0);
+        assert_near(map_state.power(1, 0), 50.0 / 230.0);
+        assert_eq!(map_state.power(0, 1), 0.0);
+    }
+
+    #[test]
+    fn activity_follows_throughput() {
+        let mut map_state = map(&[(1, 0, MapNode::Furnace), (0, 1, MapNode::Cargo)]);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::Ice, 5.0);
+
+        map_state.tick(&buildings, 1.0);
+        assert_near(map_state.activity(1, 0), 1.0 / 3.0);
+        assert_near(map_state.activity(0, 0), 30.0 / 50.0);
+        assert_eq!(map_state.activity(0, 1), 0.0);
+
+        map_state.harvest(Cargo::Copper, 5.0);
+        map_state.tick(&buildings, 1.0);
+        assert_near(map_state.activity(1, 0), 2.0 / 3.0);
+        assert_near(map_state.fill_level(), 9.9 / 118.0);
+    }
+
+    #[test]
+    fn disabled_rooms_use_no_energy_and_stop_processing() {
+        let mut map_state = map(&[(1, 0, MapNode::Furnace), (0, 1, MapNode::Cargo)]);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::Ice, 5.0);
+
+        map_state.set_enabled(1, 0, false);
+        map_state.tick(&buildings, 1.0);
+        assert_eq!(map_state.energy(), (50.0, 5.0));
+        assert_eq!(map_state.power(1, 0), 0.0);
+        assert_eq!(map_state.output_rate(1, 0, 0), 0.0);
+        assert_eq!(map_state.cargo_count(Cargo::Ice).0, 5.0);
+
+        map_state.set_enabled(1, 0, true);
+        map_state.tick(&buildings, 1.0);
+        assert_eq!(map_state.power(1, 0), 1.0);
+        assert_near(map_state.output_rate(1, 0, 0), 0.4);
+
+        map_state.set_enabled(0, 0, false);
+        assert!(map_state.is_enabled(0, 0));
+    }
+
+    #[test]
+    fn process_status_reports_missing_input_and_full_output() {
+        let mut map_state = map(&[(1, 0, MapNode::Furnace), (0, 1, MapNode::Cargo)]);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        let furnace = &buildings.get(&MapNode::Furnace).unwrap().processes[0];
+
+        assert_eq!(
+            map_state.process_status(1, 0, furnace),
+            ProcessStatus::Starved(Cargo::Ice)
+        );
+        map_state.harvest(Cargo::Ice, 5.0);
+        assert_eq!(
+            map_state.process_status(1, 0, furnace),
+            ProcessStatus::Running
+        );
+        map_state.harvest(Cargo::Water, 100.0);
+        assert_eq!(
+            map_state.process_status(1, 0, furnace),
+            ProcessStatus::OutputFull
+        );
+    }
+
+    #[test]
+    fn priority_override_is_dropped_with_the_room() {
+        let mut map_state = map(&[(1, 0, MapNode::Furnace)]);
+        let buildings = buildings();
+
+        map_state.set_priority(1, 0, PowerPriority::Low);
+        assert_eq!(map_state.priority(1, 0, &buildings), PowerPriority::Low);
+
+        map_state.add_room(1, 0, MapNode::Crusher);
+        assert_eq!(map_state.priority(1, 0, &buildings), PowerPriority::Normal);
+    }
+
+    #[test]
+    fn logistics_moves_cargo_through_room_buffers() {
+        let mut map_state = map(&[(1, 0, MapNode::Furnace), (0, 1, MapNode::Cargo)]);
+        map_state.set_logistics(true);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::Ice, 5.0);
+
+        map_state.tick(&buildings, 1.0);
+        map_state.tick(&buildings, 1.0);
+
+        let inventory = map_state.inventory(1, 0).unwrap();
+        assert_near(inventory.input[&Cargo::Ice], 1.2);
+        assert_near(inventory.output[&Cargo::Water], 0.4);
+        assert_near(map_state.cargo_count(Cargo::Ice).0, 3.0);
+        assert_near(map_state.cargo_count(Cargo::Water).0, 0.4);
+    }
+
+    #[test]
+    fn logistics_is_slower_over_longer_paths() {
+        let mut map_state = map(&[
+            (1, 0, MapNode::EmptyRoom),
+            (2, 0, MapNode::EmptyRoom),
+            (3, 0, MapNode::Furnace),
+        ]);
+        map_state.set_logistics(true);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::Ice, 5.0);
+
+        map_state.tick(&buildings, 1.0);
+
+        let inventory = map_state.inventory(3, 0).unwrap();
+        assert_eq!(map_state.distance(3, 0), Some(3));
+        assert_near(inventory.input[&Cargo::Ice], 0.0);
+        assert_near(inventory.output[&Cargo::Water], 1.0 / 3.0);
+        assert_near(map_state.cargo_count(Cargo::Ice).0, 5.0 - 1.0 / 3.0);
+    }
+
+    #[test]
+    fn logistics_feeds_rooms_from_neighbour_output() {
+        let mut map_state = map(&[(1, 0, MapNode::Furnace), (2, 0, MapNode::Enrichment)]);
+        map_state.set_logistics(true);
+        let buildings = buildings();
+        map_state.tick(&buildings, 0.0);
+        map_state.harvest(Cargo::Uranium, 5.0);
+
+        for _ in 0..5 {
+            map_state.tick(&buildings, 1.0);
+        }
+
+        let inventory = map_state.inventory(2, 0).unwrap();
+        assert!(inventory.input[&Cargo::UraniumRods] > 0.0);
+        assert_near(map_state.cargo_count(Cargo::UraniumRods).0, 0.0);
+    }
+
+    #[test]
+    fn shield_charges_from_power_and_absorbs_impacts() {
+        let mut map_state = map(&[(1, 0, MapNode::Shield)]);
+        let buildings = buildings();
+
+        map_state.tick(&buildings, 1.0);
+        assert_near(map_state.shield_charge(1, 0), 0.2);
+        assert!(!map_state.absorb_impact(1, 0, 30.0));
+
+        map_state.tick(&buildings, 1.0);
+        assert!(map_state.absorb_impact(1, 0, 30.0));
+        assert_near(map_state.shield_charge(1, 0), 0.1);
+
+        map_state.tick(&buildings, 60.0);
+        assert_eq!(map_state.shield_charge(1, 0), 1.0);
+
+        map_state.remove_room(1, 0, MapLayer::Main);
+        map_state.tick(&buildings, 1.0);
+        assert_eq!(map_state.shield_charge(1, 0), 0.0);
+        assert!(!map_state.absorb_impact(1, 0, 0.0));
+    }
+
+    #[test]
+    fn rooms_are_destroyed_when_out_of_health() {
+        let mut map_state = map(&[(1, 0, MapNode::EmptyRoom), (2, 0, MapNode::EmptyRoom)]);
+
+        map_state.damage_room(1, 0, 60.0);
+        assert_near(map_state.health(1, 0), 0.4);
+        assert!(map_state.is_room(1, 0, MapLayer::Main));
+
+        map_state.damage_room(2, 0, 10.0);
+        map_state.damage_room(1, 0, 40.0);
+        map_state.check_connectivity();
+        assert!(!map_state.is_room(1, 0, MapLayer::Main));
+        assert!(!map_state.is_room(2, 0, MapLayer::Main));
+        assert!(map_state.damage().is_empty());
+
+        map_state.damage_room(0, 0, 1000.0);
+        assert_eq!(map_state.primary_blocks(), vec![IVec2::ZERO]);
+        assert_eq!(map_state.health(0, 0), 1.0);
+    }
+
+    #[test]
+    fn repair_is_limited_by_silicon() {
+        let mut map_state = map(&[(1, 0, MapNode::Cargo)]);
+        map_state.tick(&buildings(), 0.0);
+        map_state.damage_room(1, 0, 50.0);
+        map_state.harvest(Cargo::Silicon, 4.0);
+
+        map_state.repair(1, 0);
+        assert_near(map_state.health(1, 0), 0.7);
+        assert_near(map_state.cargo_count(Cargo::Silicon).0, 0.0);
+        assert_near(map_state.repair_cost(1, 0), 6.0);
+
+        map_state.harvest(Cargo::Silicon, 10.0);
+        map_state.repair(1, 0);
+        assert_eq!(map_state.health(1, 0), 1.0);
+        assert_near(map_state.cargo_count(Cargo::Silicon).0, 4.0);
+
+        // Upgrading keeps the damage, only repairs remove it
+        map_state.damage_room(1, 0, 10.0);
+        map_state.add_room(1, 0, MapNode::Furnace);
+        assert_near(map_state.health(1, 0), 0.9);
+
+        map_state.remove_room(1, 0, MapLayer::Main);
+        map_state.add_room(1, 0, MapNode::EmptyRoom);
+        assert_eq!(map_state.health(1, 0), 1.0);
+    }
+
+    #[test]
+    fn energy_ratio_edge_cases() {
+        let mut map_state = MapState::default();
+        assert_eq!(map_state.energy_ratio(), 0.0);
+
+        map_state.energy_in_use = 5.0;
+        assert_eq!(map_state.energy_ratio(), 0.0);
+
+        map_state.energy_available = 50.0;
+        map_state.energy_in_use = 0.0;
+        assert_eq!(map_state.energy_ratio(), 1.0);
+
+        map_state.energy_in_use = 25.0;
+        assert_eq!(map_state.energy_ratio(), 0.5);
 
-        *map = new_map;
+        map_state.energy_in_use = 75.0;
+        assert_eq!(map_state.energy_ratio(), 0.0);
     }
 }
@@ -1,11 +1,15 @@
 use bevy::{dev_tools::fps_overlay::FpsOverlayConfig, prelude::*};
 
-use crate::scenes::AppState;
+use crate::{
+    components::input_map::{Action, Actions},
+    scenes::AppState,
+};
 
 use super::{
+    buildings::Buildings,
     game_cursor::GameCursor,
     map_state::{MapLayer, MapNode, MapState},
-    GameState,
+    GameState, SpawnRequest,
 };
 
 pub struct PlayerPlugin;
@@ -31,30 +35,52 @@ fn listen_inputs(
     mut next_player_state: ResMut<NextState<PlayerState>>,
     game_state: Res<State<GameState>>,
     player_state: Res<State<PlayerState>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mouse: Res<ButtonInput<MouseButton>>,
+    actions: Actions,
     // fps_overlay_config: Res<FpsOverlayConfig>,
     game_cursor: Option<Res<GameCursor>>,
-    map_state: Res<MapState>,
+    mut map_state: ResMut<MapState>,
+    buildings: Res<Buildings>,
 ) {
-    if keyboard.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(Action::Pause) {
         match game_state.get() {
             GameState::Pause => next_game_state.set(GameState::Idle),
             GameState::Idle => next_game_state.set(GameState::Pause),
+            GameState::Warp => {}
         }
     }
 
-    if mouse.just_pressed(MouseButton::Right) {
+    if actions.just_pressed(Action::Cancel) {
         next_player_state.set(PlayerState::Idle);
     }
 
-    if keyboard.just_pressed(KeyCode::KeyD) {
+    if actions.just_pressed(Action::Destroy) {
         match player_state.get() {
             PlayerState::Destruct => next_player_state.set(PlayerState::Idle),
             _ => next_player_state.set(PlayerState::Destruct),
         }
     }
 
+    if actions.just_pressed(Action::Priority) {
+        if let PlayerState::Interact(x, y) = *player_state.get() {
+            let priority = map_state.priority(x, y, &buildings).next();
+            map_state.set_priority(x, y, priority);
+        }
+    }
+
+    if actions.just_pressed(Action::Repair) {
+        if let PlayerState::Interact(x, y) = *player_state.get() {
+            map_state.repair(x, y);
+        }
+    }
+
+    if *game_state.get() == GameState::Idle {
+        for (action, def) in Action::HOTBAR.into_iter().zip(buildings.buildable()) {
+            if actions.just_pressed(action) {
+                commands.insert_resource(SpawnRequest(def.node.clone()));
+            }
+        }
+    }
+
     // if keyboard.just_pressed(KeyCode::KeyF) {
     //     commands.insert_resource(FpsOverlayConfig {
     //         enabled: !fps_overlay_config.enabled,
@@ -5,15 +5,19 @@ use bevy::{
 };
 use bevy_rapier2d::prelude::*;
 use ops::FloatPow;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rand_distr::{weighted::WeightedIndex, Distribution};
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use crate::{
     components::{
         collisions::Collisions,
-        procedural_material::{ProceduralMaterial, ProceduralMaterialPlugin},
+        procedural_material::{
+            ProceduralLayer, ProceduralMaterial, ProceduralMaterialPlugin, TextureLayer,
+        },
+        sfx::{PlaySfx, Sfx},
     },
     scenes::{AppSceneRoot, AppState},
     RandomRotation,
@@ -24,9 +28,17 @@ use super::{
     game_cursor::{CursorLayer, GameCursor},
     map_state::{Cargo, MapLayer, MapState},
     room::Room,
+    seed::ResumableRng,
+    shield::{ShieldBubble, SHIELD_IMPACT_COST},
+    simulation::SimCollider,
     GameState,
 };
 
+pub const ROCK_MODEL: &str = "rock_0.glb";
+pub const ROCK_MATERIAL: &str = "rock_material";
+pub const ROCK_SHADER: &str = "rock.wgsl";
+const ROCK_DAMAGE: f32 = 40.0;
+
 pub struct RockPlugin;
 
 impl Plugin for RockPlugin {
@@ -40,8 +52,19 @@ impl Plugin for RockPlugin {
     }
 }
 
-#[derive(Clone, Copy, EnumIter)]
-enum RockKind {
+pub struct HeadlessRockPlugin;
+
+impl Plugin for HeadlessRockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (init_headless, update_pos, rock_spawner.after(init_headless)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter, Serialize, Deserialize)]
+pub enum RockKind {
     Silicon,
     Ice,
     Copper,
@@ -50,6 +73,16 @@ enum RockKind {
 }
 
 impl RockKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RockKind::Silicon => "Silicon",
+            RockKind::Ice => "Ice",
+            RockKind::Copper => "Copper",
+            RockKind::Uranium => "Uranium",
+            RockKind::Aurelium => "Aurelium",
+        }
+    }
+
     fn probability(self) -> f32 {
         match self {
             RockKind::Silicon => 0.5,
@@ -83,16 +116,26 @@ impl RockKind {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub struct Rock {
     pub movement_speed: Vec2,
     rotation_speed: f32,
     rotation_axis: Dir3,
     scale: f32,
     kind: RockKind,
+    #[serde(default)]
+    seed: u64,
 }
 
 impl Rock {
+    fn impact(&self) -> f32 {
+        ROCK_DAMAGE * self.scale * self.movement_speed.length()
+    }
+
+    pub fn kind(&self) -> RockKind {
+        self.kind
+    }
+
     pub fn resources(&self) -> HashMap<Cargo, f32> {
         self.kind
             .resources()
@@ -124,26 +167,29 @@ fn init(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let mut rng = rand::rng();
-
     for (entity, rock, state) in rocks.iter() {
         match state {
             None => {
-                commands.entity(entity).insert((
-                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("rock_0.glb"))),
-                    LoadingState::Materials,
-                    RockState::Idle,
-                    Visibility::Hidden,
-                    Collider::ball(1.0),
-                    ActiveEvents::COLLISION_EVENTS,
-                    ActiveCollisionTypes::STATIC_STATIC,
-                ));
+                commands
+                    .entity(entity)
+                    .insert((
+                        SceneRoot(
+                            asset_server.load(GltfAssetLabel::Scene(0).from_asset(ROCK_MODEL)),
+                        ),
+                        LoadingState::Materials,
+                        Visibility::Hidden,
+                        Collider::ball(1.0),
+                        ActiveEvents::COLLISION_EVENTS,
+                        ActiveCollisionTypes::STATIC_STATIC,
+                    ))
+                    .insert_if_new(RockState::Idle);
             }
             Some(LoadingState::Materials) => {
+                let mut rng = StdRng::seed_from_u64(rock.seed);
                 for child in children.iter_descendants(entity) {
                     if !gltf_materials
                         .get(child)
-                        .map_or(false, |m| m.0 == "rock_material")
+                        .map_or(false, |m| m.0 == ROCK_MATERIAL)
                     {
                         continue;
                     }
@@ -194,7 +240,7 @@ fn init(
                     commands
                         .entity(child)
                         .remove::<MeshMaterial3d<StandardMaterial>>()
-                        .insert(RockMaterial::new(rand::random::<f32>() * 1000.0));
+                        .insert(RockMaterial::new(rng.random::<f32>() * 1000.0));
                     commands
                         .entity(entity)
                         .insert(LoadingState::Done)
@@ -206,12 +252,22 @@ fn init(
     }
 }
 
+fn init_headless(mut commands: Commands, rocks: Query<Entity, (With<Rock>, Without<SimCollider>)>) {
+    for entity in rocks.iter() {
+        commands
+            .entity(entity)
+            .insert(SimCollider(1.0))
+            .insert_if_new(RockState::Idle);
+    }
+}
+
 fn update_pos(
     mut commands: Commands,
-    mut rocks: Query<(Entity, &Rock, &RockState, &mut Transform), Without<Room>>,
+    mut rocks: Query<(Entity, &mut Rock, &RockState, &mut Transform), Without<Room>>,
     mut map_state: ResMut<MapState>,
     build_entity: Option<Res<BuildEntity>>,
     rooms: Query<&Transform, With<Room>>,
+    shields: Query<&GlobalTransform, With<ShieldBubble>>,
     collisions: Res<Collisions>,
     time: Res<Time>,
 ) {
@@ -219,7 +275,7 @@ fn update_pos(
     let min = Vec2::from(GameCursor::game_to_world(min.x, min.y, CursorLayer::Room)) - 40.0;
     let max = Vec2::from(GameCursor::game_to_world(max.x, max.y, CursorLayer::Room)) + 40.0;
 
-    for (entity, rock, rock_state, mut transform) in rocks.iter_mut() {
+    for (entity, mut rock, rock_state, mut transform) in rocks.iter_mut() {
         if transform.translation.x <= min.x
             || transform.translation.x >= max.x
             || transform.translation.y <= min.y
@@ -239,6 +295,29 @@ fn update_pos(
             TAU * rock.rotation_speed * time.delta_secs(),
         );
 
+        let mut deflected = false;
+        for shield in collisions.get(entity) {
+            let Ok(shield_transform) = shields.get(*shield) else {
+                continue;
+            };
+            let center = shield_transform.translation().xy();
+            let normal = (transform.translation.xy() - center).normalize_or_zero();
+            let approach = rock.movement_speed.dot(normal);
+            if approach >= 0.0 {
+                continue;
+            }
+            let IVec2 { x, y } = GameCursor::world_to_game(center.x, center.y, CursorLayer::Room);
+            if map_state.absorb_impact(x, y, SHIELD_IMPACT_COST * rock.scale) {
+                rock.movement_speed -= 2.0 * approach * normal;
+                commands.trigger(PlaySfx(Sfx::RockImpact));
+                deflected = true;
+                break;
+            }
+        }
+        if deflected {
+            continue;
+        }
+
         for room in collisions.get(entity) {
             if let Some(BuildEntity(build_entity)) = build_entity.as_deref() {
                 if build_entity == room {
@@ -252,8 +331,10 @@ fn update_pos(
                     CursorLayer::Room,
                 );
                 if map_state.is_room(x, y, MapLayer::Main) {
-                    map_state.remove_room(x, y, MapLayer::Main);
+                    map_state.damage_room(x, y, rock.impact());
+                    commands.trigger(PlaySfx(Sfx::RockImpact));
                     commands.entity(entity).try_despawn_recursive();
+                    break;
                 }
             }
         }
@@ -286,15 +367,58 @@ fn ray_intersects_circle(
     t_closest - offset >= 0.0 || t_closest + offset >= 0.0
 }
 
+#[derive(Resource)]
+pub struct RockSpawner {
+    rng: ResumableRng,
+    since_spawned: f32,
+    since_spawned_aurelium: f32,
+}
+
+// What a save needs to continue the waves where they left off
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RockSpawnerProgress {
+    draws: u64,
+    since_spawned: f32,
+    since_spawned_aurelium: f32,
+}
+
+impl RockSpawner {
+    pub fn new(seed: u64) -> Self {
+        Self::resume(seed, &RockSpawnerProgress::default())
+    }
+
+    pub fn resume(seed: u64, progress: &RockSpawnerProgress) -> Self {
+        Self {
+            rng: ResumableRng::resume(seed, progress.draws),
+            since_spawned: progress.since_spawned,
+            since_spawned_aurelium: progress.since_spawned_aurelium,
+        }
+    }
+
+    pub fn progress(&self) -> RockSpawnerProgress {
+        RockSpawnerProgress {
+            draws: self.rng.draws(),
+            since_spawned: self.since_spawned,
+            since_spawned_aurelium: self.since_spawned_aurelium,
+        }
+    }
+}
+
 fn rock_spawner(
     mut commands: Commands,
+    mut spawner: ResMut<RockSpawner>,
     root_entity: Res<AppSceneRoot>,
     map_state: Res<MapState>,
     time: Res<Time>,
-    mut last_spawned: Local<f32>,
-    mut last_spawned_aurelium: Local<f32>,
 ) {
-    let mut rng = rand::rng();
+    let RockSpawner {
+        rng,
+        since_spawned,
+        since_spawned_aurelium,
+    } = spawner.as_mut();
+
+    *since_spawned += time.delta_secs();
+    *since_spawned_aurelium += time.delta_secs();
 
     let (min, max) = map_state.get_bounds();
     let min = GameCursor::game_to_world(min.x, min.y, CursorLayer::Room);
@@ -304,7 +428,7 @@ fn rock_spawner(
 
     let mut rocks = vec![];
 
-    if time.elapsed_secs() - *last_spawned_aurelium >= 180.0 {
+    if *since_spawned_aurelium >= 180.0 {
         let spawn_point1 = Vec2::new(
             rng.random_range(min.x - room_radius..=max.x + room_radius),
             max.y + 30.0,
@@ -331,13 +455,13 @@ fn rock_spawner(
         let speed = 10.0;
 
         if success {
-            *last_spawned_aurelium = time.elapsed_secs();
+            *since_spawned_aurelium = 0.0;
             rocks.push((spawn_point1, flight_dir1 * speed, RockKind::Aurelium));
             rocks.push((spawn_point2, flight_dir2 * speed, RockKind::Aurelium));
         }
     }
 
-    if time.elapsed_secs() - *last_spawned >= 1.0 {
+    if *since_spawned >= 1.0 {
         let rand_y = rng.random_range(min.y - 10.0..=max.y + 10.0);
         let flight_dir = Vec2::NEG_X;
 
@@ -355,10 +479,10 @@ fn rock_spawner(
         let speed = 2.0;
 
         let weighted = WeightedIndex::new(RockKind::iter().map(RockKind::probability)).unwrap();
-        let kind = RockKind::iter().nth(weighted.sample(&mut rng)).unwrap();
+        let kind = RockKind::iter().nth(weighted.sample(rng)).unwrap();
 
         if success {
-            *last_spawned = time.elapsed_secs();
+            *since_spawned = 0.0;
             rocks.push((spawn_point, flight_dir * speed, kind));
         }
     }
@@ -369,9 +493,10 @@ fn rock_spawner(
             Rock {
                 movement_speed,
                 rotation_speed: rng.random_range(-1.0..1.0),
-                rotation_axis: Transform::from_rotation(Quat::random()).forward(),
+                rotation_axis: Transform::from_rotation(Quat::random(rng)).forward(),
                 scale,
                 kind,
+                seed: rng.random(),
             },
             Transform::from_xyz(spawn_point.x, spawn_point.y, 2.0)
                 .looking_to(movement_speed.normalize().extend(0.0), Vec3::Z)
@@ -380,7 +505,7 @@ fn rock_spawner(
     }
 }
 
-#[derive(Component, ShaderType, Clone)]
+#[derive(Component, ShaderType, Clone, PartialEq)]
 struct RockMaterial {
     seed: f32,
     time: f32,
@@ -399,10 +524,19 @@ impl RockMaterial {
 
 impl ProceduralMaterial for RockMaterial {
     fn shader() -> &'static str {
-        "rock.wgsl"
+        ROCK_SHADER
     }
 
-    fn size() -> (u32, u32) {
-        (64, 64)
+    // Rocks don't glow, the emissive layer is left out
+    fn layers() -> Vec<ProceduralLayer> {
+        [
+            TextureLayer::Diffuse,
+            TextureLayer::Metallic,
+            TextureLayer::Roughness,
+            TextureLayer::Normal,
+        ]
+        .into_iter()
+        .map(|layer| ProceduralLayer::new(layer, (64, 64)))
+        .collect()
     }
 }
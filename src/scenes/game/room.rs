@@ -1,15 +1,21 @@
-use bevy::{gltf::GltfMaterialName, prelude::*, render::render_resource::ShaderType};
+use bevy::{prelude::*, render::render_resource::ShaderType};
 use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use strum::IntoEnumIterator;
 
 use crate::{
-    components::procedural_material::{ProceduralMaterial, ProceduralMaterialPlugin},
+    components::procedural_material::{
+        ProceduralLayer, ProceduralMaterial, ProceduralMaterialPlugin, ProceduralPipeline,
+        TextureLayer,
+    },
     scenes::AppState,
 };
 
 use super::{
-    builder::Ready,
+    building_model::{BuildingModel, BuildingModelPlugin, ModelParts},
     game_cursor::{CursorLayer, GameCursor},
     player::PlayerState,
+    seed::GameRng,
     GameState,
 };
 
@@ -18,81 +24,66 @@ pub struct RoomPlugin;
 impl Plugin for RoomPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(ProceduralMaterialPlugin::<RoomFloorMaterial>::default())
+            .add_plugins(BuildingModelPlugin::<Room>::default())
             .add_systems(
                 Update,
-                (update_floor_material, (update, init).chain())
+                (update_floor_material, (init, update).chain())
                     .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
             );
     }
 }
 
+pub const FLOOR_MATERIAL: &str = "room_floor";
+pub const FLOOR_SHADER: &str = "room_floor.wgsl";
+
 #[derive(Component)]
 pub struct Room;
 
-#[derive(Component, PartialEq)]
-enum RoomState {
-    Materials,
-    Done { floor: Entity },
+impl BuildingModel for Room {
+    fn model() -> &'static str {
+        "room.glb"
+    }
+
+    fn parts() -> &'static [&'static str] {
+        &[FLOOR_MATERIAL]
+    }
+
+    fn collider() -> Option<Collider> {
+        Some(Collider::cuboid(1.0, 1.0))
+    }
+}
+
+pub fn replace_floor_material(
+    commands: &mut Commands,
+    parts: &ModelParts,
+    material: RoomFloorMaterial,
+) {
+    for floor in parts.get(FLOOR_MATERIAL) {
+        commands
+            .entity(floor)
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert(material.clone());
+    }
 }
 
 fn init(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    rooms: Query<(Entity, Option<&RoomState>), With<Room>>,
-    children: Query<&Children>,
-    gltf_materials: Query<&GltfMaterialName>,
+    rooms: Query<&ModelParts, (With<Room>, Added<ModelParts>)>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for (entity, state) in rooms.iter() {
-        match state {
-            None => {
-                commands.entity(entity).insert((
-                    SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("room.glb"))),
-                    RoomState::Materials,
-                    Visibility::Hidden,
-                    Collider::cuboid(1.0, 1.0),
-                    ActiveEvents::COLLISION_EVENTS,
-                    ActiveCollisionTypes::STATIC_STATIC,
-                ));
-            }
-            Some(RoomState::Materials) => {
-                for child in children.iter_descendants(entity) {
-                    if gltf_materials
-                        .get(child)
-                        .map_or(false, |m| m.0 == "room_floor")
-                    {
-                        commands
-                            .entity(child)
-                            .remove::<MeshMaterial3d<StandardMaterial>>()
-                            .insert(RoomFloorMaterial::new(
-                                rand::random::<f32>() * 1000.0,
-                                0.95,
-                                1.0,
-                            ));
-                        commands
-                            .entity(entity)
-                            .insert(Ready)
-                            .insert(RoomState::Done { floor: child })
-                            .insert(Visibility::Inherited);
-                    }
-                }
-            }
-            Some(RoomState::Done { .. }) => {}
-        }
+    for parts in rooms.iter() {
+        let material = RoomFloorMaterial::new(rng.random::<f32>() * 1000.0, 0.95, 1.0);
+        replace_floor_material(&mut commands, parts, material);
     }
 }
 
 fn update(
-    mut rooms: Query<(&RoomState, &Transform), With<Room>>,
+    rooms: Query<(&ModelParts, &Transform), With<Room>>,
     mut floor_materials: Query<&mut RoomFloorMaterial>,
     game_cursor: Option<Res<GameCursor>>,
     player_state: Res<State<PlayerState>>,
 ) {
-    for (loading_state, transform) in rooms.iter_mut() {
-        let floor = match loading_state {
-            RoomState::Done { floor } => floor,
-            _ => continue,
-        };
-
+    for (parts, transform) in rooms.iter() {
         let Vec2 { x, y } = transform.translation.xy();
         let IVec2 { x, y } = GameCursor::world_to_game(x, y, CursorLayer::Room);
 
@@ -102,8 +93,11 @@ fn update(
         };
 
         if *player_state.get() == PlayerState::Idle {
-            let mut floor_material = floor_materials.get_mut(*floor).unwrap();
-            floor_material.time_multiplier = if is_selected { 100.0 } else { 1.0 };
+            for floor in parts.get(FLOOR_MATERIAL) {
+                if let Ok(mut floor_material) = floor_materials.get_mut(floor) {
+                    floor_material.time_multiplier = if is_selected { 100.0 } else { 1.0 };
+                }
+            }
         }
     }
 }
@@ -114,7 +108,7 @@ fn update_floor_material(mut settings: Query<&mut RoomFloorMaterial>, time: Res<
     }
 }
 
-#[derive(Component, ShaderType, Clone)]
+#[derive(Component, ShaderType, Clone, PartialEq)]
 pub struct RoomFloorMaterial {
     seed: f32,
     time: f32,
@@ -137,10 +131,21 @@ impl RoomFloorMaterial {
 
 impl ProceduralMaterial for RoomFloorMaterial {
     fn shader() -> &'static str {
-        "room_floor.wgsl"
+        FLOOR_SHADER
+    }
+
+    fn layers() -> Vec<ProceduralLayer> {
+        TextureLayer::iter()
+            .map(|layer| ProceduralLayer::new(layer, (36, 36)))
+            .collect()
+    }
+
+    fn pipeline() -> ProceduralPipeline {
+        ProceduralPipeline::Compute
     }
 
-    fn size() -> (u32, u32) {
-        (36, 36)
+    // The emission drifts slowly, no need to follow the frame rate
+    fn update_rate() -> Option<f32> {
+        Some(15.0)
     }
 }
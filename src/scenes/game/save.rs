@@ -0,0 +1,389 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::scenes::{AppSceneRoot, AppState};
+
+use super::{
+    builder::{spawn_node, ActionState, BuildEntity, Enabled, HighlightState, NodeState},
+    game_cursor::{CursorLayer, GameCursor},
+    hook::{Hook, HookState},
+    map_state::{Cargo, Inventory, LogisticsMode, MapNode, MapState, PowerPriority},
+    rock::{Rock, RockSpawner, RockSpawnerProgress, RockState},
+    seed::{GameRng, GameSeed},
+    setup,
+    warp::WarpState,
+    GameClock, RunStats,
+};
+
+pub const SAVE_SLOTS: usize = 3;
+pub const SAVE_SLOT_NAMES: [&str; SAVE_SLOTS] = ["Slot 1", "Slot 2", "Slot 3"];
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Game), load.after(setup))
+            .add_systems(
+                Update,
+                save.run_if(in_state(AppState::Game).and(resource_exists::<SaveRequest>)),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct SaveRequest(pub usize);
+
+#[derive(Resource)]
+pub struct PendingLoad(pub SaveData);
+
+#[derive(Clone, Serialize, Deserialize)]
+enum HookSave {
+    Idle,
+    Flying {
+        dir: Dir2,
+        length: f32,
+    },
+    Returning {
+        dir: Dir2,
+        length: f32,
+        rock: Option<usize>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct RockSave {
+    rock: Rock,
+    transform: Transform,
+    hooked: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub timestamp: u64,
+    pub elapsed: f32,
+    rooms: Vec<(IVec2, MapNode)>,
+    cargo: Vec<(Cargo, f32)>,
+    rocks: Vec<RockSave>,
+    hooks: Vec<(IVec2, HookSave)>,
+    #[serde(default)]
+    stats: RunStats,
+    #[serde(default)]
+    warp: WarpState,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    priorities: Vec<(IVec2, PowerPriority)>,
+    #[serde(default)]
+    logistics: bool,
+    #[serde(default)]
+    inventories: Vec<(IVec2, Inventory)>,
+    #[serde(default)]
+    damage: Vec<(IVec2, f32)>,
+    #[serde(default)]
+    disabled: Vec<IVec2>,
+    #[serde(default)]
+    hook_modes: Vec<(IVec2, bool)>,
+    #[serde(default)]
+    rng_draws: u64,
+    #[serde(default)]
+    rock_spawner: RockSpawnerProgress,
+}
+
+impl SaveData {
+    pub fn read(slot: usize) -> Option<Self> {
+        let text = std::fs::read_to_string(slot_path(slot)).ok()?;
+        match ron::from_str(&text) {
+            Ok(save) => Some(save),
+            Err(err) => {
+                warn!("Failed to parse save slot {slot}: {err}");
+                None
+            }
+        }
+    }
+
+    pub fn latest() -> Option<Self> {
+        (0..SAVE_SLOTS)
+            .filter_map(Self::read)
+            .max_by_key(|save| save.timestamp)
+    }
+
+    fn write(&self, slot: usize) -> Result<(), String> {
+        let path = slot_path(slot);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let text =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+
+    pub fn spawn_thumbnail(&self, parent: &mut ChildBuilder, size: f32) {
+        // Starting from a room keeps an empty save from overflowing the bounds
+        let start = self.rooms.first().map_or(IVec2::ZERO, |(c, _)| *c);
+        let min = self.rooms.iter().fold(start, |acc, (c, _)| acc.min(*c));
+        let max = self.rooms.iter().fold(start, |acc, (c, _)| acc.max(*c));
+        let cells = (max - min + 1).max_element().max(1) as f32;
+        let cell = size / cells;
+
+        parent
+            .spawn((
+                Node {
+                    width: Val::Px(size),
+                    height: Val::Px(size),
+                    flex_shrink: 0.0,
+                    ..Default::default()
+                },
+                BackgroundColor(Color::BLACK),
+            ))
+            .with_children(|parent| {
+                for (IVec2 { x, y }, node) in &self.rooms {
+                    parent.spawn((
+                        Node {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px((x - min.x) as f32 * cell),
+                            top: Val::Px((max.y - y) as f32 * cell),
+                            width: Val::Px(cell - 1.0),
+                            height: Val::Px(cell - 1.0),
+                            ..Default::default()
+                        },
+                        BackgroundColor(thumbnail_color(node)),
+                    ));
+                }
+            });
+    }
+
+    pub fn title(&self) -> String {
+        let elapsed = self.elapsed as u64;
+        format!(
+            "{}\nPlayed {:02}:{:02}:{:02}",
+            format_timestamp(self.timestamp),
+            elapsed / 3600,
+            elapsed / 60 % 60,
+            elapsed % 60
+        )
+    }
+}
+
+fn slot_path(slot: usize) -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("avaruus")
+        .join(format!("slot_{slot}.ron"))
+}
+
+fn thumbnail_color(node: &MapNode) -> Color {
+    match node {
+        MapNode::PrimaryBlock => Color::srgb(1.0, 1.0, 1.0),
+        MapNode::EmptyRoom => Color::srgb(0.4, 0.4, 0.4),
+        MapNode::Furnace => Color::srgb(1.0, 0.4, 0.0),
+        MapNode::Generator => Color::srgb(0.2, 1.0, 0.2),
+        MapNode::Crusher => Color::srgb(0.6, 0.5, 0.4),
+        MapNode::Cargo => Color::srgb(0.3, 0.5, 1.0),
+        MapNode::Hook => Color::srgb(1.0, 1.0, 0.2),
+        MapNode::Enrichment => Color::srgb(0.8, 0.2, 1.0),
+        MapNode::Shield => Color::srgb(0.3, 0.8, 1.0),
+        MapNode::Custom(_) => Color::srgb(0.2, 0.9, 0.9),
+    }
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        secs / 3600,
+        secs / 60 % 60
+    )
+}
+
+fn save(
+    mut commands: Commands,
+    request: Res<SaveRequest>,
+    map_state: Res<MapState>,
+    clock: Res<GameClock>,
+    run_stats: Res<RunStats>,
+    warp_state: Res<WarpState>,
+    seed: Res<GameSeed>,
+    rng: Res<GameRng>,
+    rock_spawner: Res<RockSpawner>,
+    build_entity: Option<Res<BuildEntity>>,
+    rocks: Query<(Entity, &Rock, &RockState, &Transform)>,
+    hooks: Query<(Entity, &Hook, &HookState, &GlobalTransform)>,
+) {
+    let SaveRequest(slot) = *request;
+    commands.remove_resource::<SaveRequest>();
+
+    let rock_indices = rocks
+        .iter()
+        .enumerate()
+        .map(|(i, (entity, ..))| (entity, i))
+        .collect::<HashMap<_, _>>();
+
+    let hooks = hooks
+        .iter()
+        .filter(|(entity, ..)| !build_entity.as_ref().is_some_and(|e| e.0 == *entity))
+        .map(|(_, Hook(automatic), state, transform)| {
+            let Vec3 { x, y, .. } = transform.translation();
+            let state = match *state {
+                HookState::Idle => HookSave::Idle,
+                HookState::Flying { dir, length } => HookSave::Flying { dir, length },
+                HookState::Returning { dir, length, rock } => HookSave::Returning {
+                    dir,
+                    length,
+                    rock: rock.and_then(|rock| rock_indices.get(&rock).cloned()),
+                },
+            };
+            (
+                GameCursor::world_to_game(x, y, CursorLayer::Room),
+                state,
+                *automatic,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let save = SaveData {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        elapsed: clock.0,
+        rooms: map_state.rooms(),
+        cargo: map_state.cargo(),
+        rocks: rocks
+            .iter()
+            .map(|(_, rock, state, transform)| RockSave {
+                rock: rock.clone(),
+                transform: *transform,
+                hooked: *state == RockState::Hooked,
+            })
+            .collect(),
+        hooks: hooks
+            .iter()
+            .map(|(pos, state, _)| (*pos, state.clone()))
+            .collect(),
+        stats: run_stats.clone(),
+        warp: warp_state.clone(),
+        seed: seed.0,
+        priorities: map_state.priorities(),
+        logistics: map_state.logistics(),
+        inventories: map_state.inventories(),
+        damage: map_state.damage(),
+        disabled: map_state.disabled(),
+        hook_modes: hooks
+            .iter()
+            .map(|(pos, _, automatic)| (*pos, *automatic))
+            .collect(),
+        rng_draws: rng.draws(),
+        rock_spawner: rock_spawner.progress(),
+    };
+
+    match save.write(slot) {
+        Ok(()) => info!("Saved to slot {slot}"),
+        Err(err) => warn!("Failed to write save slot {slot}: {err}"),
+    }
+}
+
+pub(super) fn load(
+    mut commands: Commands,
+    mut map_state: ResMut<MapState>,
+    mut clock: ResMut<GameClock>,
+    mut run_stats: ResMut<RunStats>,
+    mut warp_state: ResMut<WarpState>,
+    mut seed: ResMut<GameSeed>,
+    mut logistics: ResMut<LogisticsMode>,
+    root_entity: Res<AppSceneRoot>,
+    pending_load: Option<Res<PendingLoad>>,
+) {
+    let Some(PendingLoad(save)) = pending_load.as_deref() else {
+        return;
+    };
+    commands.remove_resource::<PendingLoad>();
+
+    map_state.restore(
+        save.rooms.iter().cloned(),
+        save.cargo.iter().cloned(),
+        save.priorities.iter().cloned(),
+        save.inventories.iter().cloned(),
+        save.damage.iter().cloned(),
+        save.disabled.iter().cloned(),
+    );
+    map_state.set_logistics(save.logistics);
+    *logistics = LogisticsMode(save.logistics);
+    clock.0 = save.elapsed;
+    *run_stats = save.stats.clone();
+    *warp_state = save.warp.clone();
+    *seed = GameSeed(save.seed);
+    commands.insert_resource(GameRng::resume(save.seed, save.rng_draws));
+    commands.insert_resource(RockSpawner::resume(save.seed, &save.rock_spawner));
+
+    let rocks = save
+        .rocks
+        .iter()
+        .map(|save| {
+            let mut rock = commands.spawn((save.rock.clone(), save.transform));
+            if save.hooked {
+                rock.insert(RockState::Hooked);
+            }
+            let rock = rock.id();
+            commands.entity(root_entity.world).add_child(rock);
+            rock
+        })
+        .collect::<Vec<_>>();
+
+    for (pos, node) in &save.rooms {
+        let translation = GameCursor::game_to_world(pos.x, pos.y, CursorLayer::Room).extend(0.0);
+        let entity = spawn_node(&mut commands, node)
+            .insert((
+                NodeState {
+                    action: ActionState::Idle,
+                    highlight: HighlightState::None,
+                },
+                Transform::from_translation(translation),
+            ))
+            .id();
+        commands.entity(root_entity.world).add_child(entity);
+
+        let hook = match node {
+            MapNode::PrimaryBlock => {
+                let hook = commands.spawn((Hook(false), Enabled)).id();
+                commands.entity(entity).add_child(hook);
+                hook
+            }
+            MapNode::Hook => entity,
+            _ => continue,
+        };
+
+        if let Some((_, automatic)) = save.hook_modes.iter().find(|(p, _)| p == pos) {
+            commands.entity(hook).insert(Hook(*automatic));
+        }
+
+        let Some((_, state)) = save.hooks.iter().find(|(p, _)| p == pos) else {
+            continue;
+        };
+
+        commands.entity(hook).insert(match *state {
+            HookSave::Idle => HookState::Idle,
+            HookSave::Flying { dir, length } => HookState::Flying { dir, length },
+            HookSave::Returning { dir, length, rock } => HookState::Returning {
+                dir,
+                length,
+                rock: rock.and_then(|i| rocks.get(i).cloned()),
+            },
+        });
+    }
+}
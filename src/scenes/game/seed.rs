@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+
+use crate::scenes::AppState;
+
+use super::{rock::RockSpawner, save};
+
+pub struct SeedPlugin;
+
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameSeed::from_args())
+            .insert_resource(GameRng::new(0))
+            .add_systems(OnEnter(AppState::Game), reset.before(save::load));
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GameSeed(pub u64);
+
+impl GameSeed {
+    pub fn random() -> Self {
+        Self(rand::random::<u32>() as u64)
+    }
+
+    pub fn from_args() -> Self {
+        let mut args = std::env::args().skip_while(|arg| arg != "--seed").skip(1);
+        args.next()
+            .and_then(|seed| seed.parse().ok())
+            .map_or_else(Self::random, Self)
+    }
+
+    pub fn background(&self) -> f32 {
+        StdRng::seed_from_u64(self.0).random()
+    }
+}
+
+// StdRng that draws whole u64s and counts them, so a save can resume the
+// stream by replaying the count
+pub struct ResumableRng {
+    rng: StdRng,
+    draws: u64,
+}
+
+impl ResumableRng {
+    pub fn resume(seed: u64, draws: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..draws {
+            rng.next_u64();
+        }
+        Self { rng, draws }
+    }
+
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+}
+
+impl RngCore for ResumableRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(ResumableRng);
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self::resume(seed, 0)
+    }
+
+    pub fn resume(seed: u64, draws: u64) -> Self {
+        Self(ResumableRng::resume(!seed, draws))
+    }
+}
+
+// Fresh streams for a new game, loading a save replaces them afterwards
+fn reset(mut commands: Commands, seed: Res<GameSeed>) {
+    info!("Seed {}", seed.0);
+    commands.insert_resource(GameRng::new(seed.0));
+    commands.insert_resource(RockSpawner::new(seed.0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumed_rng_continues_the_stream() {
+        let mut rng = ResumableRng::resume(7, 0);
+        let _: f32 = rng.random();
+        let _: u64 = rng.random();
+        let mut bytes = [0; 12];
+        rng.fill_bytes(&mut bytes);
+
+        let mut resumed = ResumableRng::resume(7, rng.draws());
+        assert_eq!(rng.draws(), 4);
+        for _ in 0..10 {
+            assert_eq!(rng.random::<u32>(), resumed.random::<u32>());
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::scenes::AppState;
+
+use super::{
+    builder::Ready,
+    game_cursor::{CursorLayer, GameCursor},
+    map_state::MapState,
+    simulation::SimCollider,
+    GameState,
+};
+
+pub const SHIELD_RADIUS: f32 = 4.0;
+pub const SHIELD_IMPACT_COST: f32 = 100.0;
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (update, init)
+                .chain()
+                .run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+        );
+    }
+}
+
+pub struct HeadlessShieldPlugin;
+
+impl Plugin for HeadlessShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, init_headless);
+    }
+}
+
+#[derive(Component)]
+pub struct Shield;
+
+#[derive(Component)]
+pub struct ShieldBubble;
+
+#[derive(Component)]
+struct BubbleMaterial(Handle<StandardMaterial>);
+
+#[derive(Component, PartialEq)]
+enum ShieldState {
+    Done { bubble: Entity },
+}
+
+fn init(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    shields: Query<(Entity, Option<&ShieldState>), With<Shield>>,
+) {
+    for (entity, state) in shields.iter() {
+        match state {
+            None => {
+                let material = materials.add(StandardMaterial {
+                    base_color: Color::srgba(0.3, 0.8, 1.0, 0.0),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..Default::default()
+                });
+                let bubble = commands
+                    .spawn((
+                        ShieldBubble,
+                        BubbleMaterial(material.clone()),
+                        Mesh3d(meshes.add(Sphere::new(SHIELD_RADIUS))),
+                        MeshMaterial3d(material),
+                        Transform::from_xyz(0.0, 0.0, 1.0).with_scale(Vec3::new(1.0, 1.0, 0.25)),
+                        Collider::ball(SHIELD_RADIUS),
+                        ActiveEvents::COLLISION_EVENTS,
+                        ActiveCollisionTypes::STATIC_STATIC,
+                    ))
+                    .id();
+                let emitter = commands
+                    .spawn((
+                        Mesh3d(meshes.add(Sphere::new(0.35))),
+                        MeshMaterial3d(materials.add(StandardMaterial {
+                            base_color: Color::srgb(0.3, 0.8, 1.0),
+                            emissive: LinearRgba::rgb(1.5, 4.0, 5.0),
+                            ..Default::default()
+                        })),
+                        Transform::from_xyz(0.0, 0.0, 0.5),
+                    ))
+                    .id();
+                commands
+                    .entity(entity)
+                    .insert((Ready, ShieldState::Done { bubble }, Visibility::Inherited))
+                    .add_children(&[bubble, emitter]);
+            }
+            Some(ShieldState::Done { .. }) => {}
+        }
+    }
+}
+
+fn init_headless(
+    mut commands: Commands,
+    shields: Query<Entity, (With<Shield>, Without<ShieldState>)>,
+) {
+    for entity in shields.iter() {
+        let bubble = commands
+            .spawn((
+                ShieldBubble,
+                Transform::default(),
+                SimCollider(SHIELD_RADIUS),
+            ))
+            .id();
+        commands
+            .entity(entity)
+            .insert(ShieldState::Done { bubble })
+            .add_child(bubble);
+    }
+}
+
+fn update(
+    bubbles: Query<(&GlobalTransform, &BubbleMaterial), With<ShieldBubble>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    map_state: Res<MapState>,
+) {
+    for (transform, BubbleMaterial(handle)) in bubbles.iter() {
+        let Vec2 { x, y } = transform.translation().xy();
+        let IVec2 { x, y } = GameCursor::world_to_game(x, y, CursorLayer::Room);
+
+        let alpha = 0.15 * map_state.shield_charge(x, y);
+        if let Some(material) = materials.get_mut(handle) {
+            if material.base_color.alpha() != alpha {
+                material.base_color.set_alpha(alpha);
+            }
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    ecs::schedule::{ExecutorKind, ScheduleLabel},
+    prelude::*,
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+    transform::TransformSystem,
+};
+use bevy_rapier2d::{prelude::CollisionEvent, rapier::geometry::CollisionEventFlags};
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+
+use crate::{
+    components::collisions::CollisionsPlugin,
+    scenes::{AppSceneRoot, AppState},
+};
+
+use super::{
+    builder::Enabled,
+    buildings::{BuildingDefs, Buildings},
+    game_cursor::{CursorLayer, GameCursor},
+    hook::{HeadlessHookPlugin, Hook},
+    map_state::{Cargo, MapLayer, MapNode, MapState, MapStatePlugin},
+    rock::{HeadlessRockPlugin, RockSpawner},
+    room::Room,
+    seed::GameSeed,
+    shield::{HeadlessShieldPlugin, Shield},
+    GameState, RunStats,
+};
+
+#[derive(Component)]
+pub struct SimCollider(pub f32);
+
+#[derive(Deserialize, Clone)]
+struct BuildStep {
+    node: MapNode,
+    x: i32,
+    y: i32,
+}
+
+#[derive(Resource)]
+struct BuildOrder(Vec<BuildStep>);
+
+#[derive(Resource)]
+struct Sampling {
+    interval: f32,
+    last: Option<f32>,
+}
+
+struct SimulationArgs {
+    minutes: f32,
+    step: f32,
+    sample: f32,
+    buildings: String,
+    build_order: String,
+    logistics: bool,
+}
+
+const USAGE: &str = "usage: --headless [--minutes N] [--step SECONDS] [--sample SECONDS] \
+[--buildings PATH] [--build-order PATH] [--logistics] [--seed N]";
+
+fn path(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{flag} needs a value"))
+}
+
+fn positive(flag: &str, value: Option<String>) -> Result<f32, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+    match value.parse::<f32>() {
+        Ok(parsed) if parsed > 0.0 && parsed.is_finite() => Ok(parsed),
+        _ => Err(format!("{flag} must be a positive number, got {value:?}")),
+    }
+}
+
+impl SimulationArgs {
+    fn parse_from(mut iter: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = Self {
+            minutes: 30.0,
+            step: 1.0 / 60.0,
+            sample: 10.0,
+            buildings: "assets/default.buildings.ron".to_string(),
+            build_order: "assets/default.build_order.ron".to_string(),
+            logistics: false,
+        };
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--minutes" => args.minutes = positive(&arg, iter.next())?,
+                "--step" => args.step = positive(&arg, iter.next())?,
+                "--sample" => args.sample = positive(&arg, iter.next())?,
+                "--buildings" => args.buildings = path(&arg, iter.next())?,
+                "--build-order" => args.build_order = path(&arg, iter.next())?,
+                "--logistics" => args.logistics = true,
+                // Handled by main and GameSeed
+                "--headless" => {}
+                "--seed" => {
+                    iter.next();
+                }
+                _ => return Err(format!("unknown argument {arg:?}")),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn read_ron<T: for<'de> Deserialize<'de>>(path: &str) -> Result<T, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    ron::from_str(&text).map_err(|e| format!("{path}: {e}"))
+}
+
+fn usage_error<T>(err: String) -> T {
+    eprintln!("{err}\n{USAGE}");
+    std::process::exit(2);
+}
+
+pub fn run() {
+    let args = SimulationArgs::parse_from(std::env::args().skip(1)).unwrap_or_else(usage_error);
+
+    let buildings = read_ron::<BuildingDefs>(&args.buildings).unwrap_or_else(usage_error);
+    let buildings = Buildings::from_defs(&buildings);
+    let build_order = read_ron::<Vec<BuildStep>>(&args.build_order).unwrap_or_else(usage_error);
+
+    let seed = GameSeed::from_args();
+    eprintln!("seed {}", seed.0);
+
+    let mut map_state = MapState::default();
+    map_state.add_primary_block(0, 0);
+    map_state.set_logistics(args.logistics);
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins.build().disable::<ScheduleRunnerPlugin>())
+        .add_plugins((StatesPlugin, TransformPlugin, HierarchyPlugin))
+        .add_plugins(CollisionsPlugin)
+        .add_plugins(MapStatePlugin)
+        .add_plugins(HeadlessRockPlugin)
+        .add_plugins(HeadlessHookPlugin)
+        .add_plugins(HeadlessShieldPlugin)
+        .add_event::<CollisionEvent>()
+        .insert_state(AppState::Game)
+        .insert_state(GameState::Idle)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            args.step,
+        )))
+        .insert_resource(buildings)
+        .insert_resource(map_state)
+        .insert_resource(RunStats::default())
+        .insert_resource(RockSpawner::new(seed.0))
+        .insert_resource(seed)
+        .insert_resource(BuildOrder(build_order))
+        .insert_resource(Sampling {
+            interval: args.sample,
+            last: None,
+        })
+        .add_systems(Update, (build, sync_nodes, sample).chain())
+        .add_systems(
+            PostUpdate,
+            collide.after(TransformSystem::TransformPropagate),
+        );
+
+    // Ambiguous system order must not depend on thread scheduling
+    for label in [PreUpdate.intern(), Update.intern(), PostUpdate.intern()] {
+        app.edit_schedule(label, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        });
+    }
+
+    let world = app.world_mut().spawn(Transform::default()).id();
+    let ui = app.world_mut().spawn_empty().id();
+    app.insert_resource(AppSceneRoot { world, ui });
+
+    app.finish();
+    app.cleanup();
+
+    print!("time,energy_available,energy_in_use,rooms,rocks_harvested");
+    for cargo in Cargo::iter() {
+        print!(",{cargo:?}");
+    }
+    println!();
+
+    let steps = (args.minutes * 60.0 / args.step).ceil() as u64;
+    for _ in 0..steps {
+        app.update();
+    }
+}
+
+fn build(
+    order: Res<BuildOrder>,
+    mut map_state: ResMut<MapState>,
+    mut run_stats: ResMut<RunStats>,
+    buildings: Res<Buildings>,
+    time: Res<Time>,
+) {
+    // Rooms destroyed by rocks are rebuilt before moving on with the order
+    let Some(BuildStep { node, x, y }) = order
+        .0
+        .iter()
+        .find(
+            |step| match map_state.node(step.x, step.y, MapLayer::Main) {
+                Some(node) => node != step.node && step.node != MapNode::EmptyRoom,
+                None => true,
+            },
+        )
+        .cloned()
+    else {
+        return;
+    };
+
+    if !map_state.is_available(x, y, node.clone()) {
+        return;
+    }
+
+    let recipe = buildings.recipe(&node);
+    if recipe
+        .iter()
+        .any(|(cargo, count)| map_state.cargo_count(cargo.clone()).0 < *count)
+    {
+        return;
+    }
+
+    for (cargo, count) in recipe {
+        map_state.harvest(cargo, -count);
+    }
+    map_state.add_room(x, y, node.clone());
+    run_stats.rooms_built += 1;
+
+    eprintln!("{:.1}s: built {node:?} at ({x}, {y})", time.elapsed_secs());
+}
+
+fn sync_nodes(
+    mut commands: Commands,
+    mut rooms: Local<BTreeMap<(i32, i32), Entity>>,
+    mut hooks: Local<BTreeMap<(i32, i32), Entity>>,
+    mut shields: Local<BTreeMap<(i32, i32), Entity>>,
+    root_entity: Res<AppSceneRoot>,
+    map_state: Res<MapState>,
+) {
+    let cells = map_state
+        .rooms()
+        .into_iter()
+        .map(|(pos, node)| ((pos.x, pos.y), node))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut sync = |entities: &mut BTreeMap<(i32, i32), Entity>,
+                    filter: &dyn Fn(&MapNode) -> bool| {
+        entities.retain(|pos, entity| {
+            let keep = cells.get(pos).is_some_and(filter);
+            if !keep {
+                commands.entity(*entity).despawn_recursive();
+            }
+            keep
+        });
+        for (&(x, y), node) in &cells {
+            if !filter(node) || entities.contains_key(&(x, y)) {
+                continue;
+            }
+            let translation = GameCursor::game_to_world(x, y, CursorLayer::Room).extend(0.0);
+            let entity = commands
+                .spawn(Transform::from_translation(translation))
+                .id();
+            commands.entity(root_entity.world).add_child(entity);
+            entities.insert((x, y), entity);
+        }
+    };
+
+    sync(&mut rooms, &|node| !matches!(node, MapNode::PrimaryBlock));
+    sync(&mut hooks, &|node| {
+        matches!(node, MapNode::PrimaryBlock | MapNode::Hook)
+    });
+    sync(&mut shields, &|node| matches!(node, MapNode::Shield));
+
+    for entity in rooms.values() {
+        commands
+            .entity(*entity)
+            .insert_if_new((Room, SimCollider(1.0)));
+    }
+
+    for entity in shields.values() {
+        commands.entity(*entity).insert_if_new(Shield);
+    }
+
+    // There is no player to throw the main hook, so every hook is automatic
+    for entity in hooks.values() {
+        commands
+            .entity(*entity)
+            .insert_if_new((Hook(true), Enabled));
+    }
+}
+
+fn collide(
+    mut collision_events: EventWriter<CollisionEvent>,
+    mut contacts: Local<BTreeSet<(Entity, Entity)>>,
+    colliders: Query<(Entity, &SimCollider, &GlobalTransform)>,
+) {
+    let colliders = colliders
+        .iter()
+        .map(|(entity, SimCollider(radius), transform)| {
+            let (scale, _, translation) = transform.to_scale_rotation_translation();
+            (entity, radius * scale.x, translation.xy())
+        })
+        .collect::<Vec<_>>();
+
+    let mut current = BTreeSet::new();
+    for (i, (e1, r1, p1)) in colliders.iter().enumerate() {
+        for (e2, r2, p2) in &colliders[i + 1..] {
+            if p1.distance_squared(*p2) <= (r1 + r2) * (r1 + r2) {
+                current.insert((*e1.min(e2), *e1.max(e2)));
+            }
+        }
+    }
+
+    for (e1, e2) in current.difference(&contacts) {
+        collision_events.send(CollisionEvent::Started(
+            *e1,
+            *e2,
+            CollisionEventFlags::empty(),
+        ));
+    }
+    for (e1, e2) in contacts.difference(&current) {
+        collision_events.send(CollisionEvent::Stopped(
+            *e1,
+            *e2,
+            CollisionEventFlags::empty(),
+        ));
+    }
+
+    *contacts = current;
+}
+
+fn sample(
+    mut sampling: ResMut<Sampling>,
+    map_state: Res<MapState>,
+    run_stats: Res<RunStats>,
+    time: Res<Time>,
+) {
+    let elapsed = time.elapsed_secs();
+    if sampling
+        .last
+        .is_some_and(|last| elapsed - last < sampling.interval)
+    {
+        return;
+    }
+    sampling.last = Some(elapsed);
+
+    let (energy_available, energy_in_use) = map_state.energy();
+    print!(
+        "{elapsed:.2},{energy_available},{energy_in_use},{},{}",
+        map_state.rooms().len(),
+        run_stats.rocks_harvested
+    );
+    for cargo in Cargo::iter() {
+        print!(",{:.3}", map_state.cargo_count(cargo).0);
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<SimulationArgs, String> {
+        SimulationArgs::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_simulation_args() {
+        let args = parse(&[
+            "--headless",
+            "--seed",
+            "7",
+            "--minutes",
+            "5",
+            "--buildings",
+            "b.ron",
+            "--logistics",
+        ])
+        .unwrap();
+        assert_eq!(args.minutes, 5.0);
+        assert_eq!(args.buildings, "b.ron");
+        assert_eq!(args.build_order, "assets/default.build_order.ron");
+        assert!(args.logistics);
+    }
+
+    #[test]
+    fn rejects_bad_simulation_args() {
+        assert!(parse(&["--minute", "5"]).is_err());
+        assert!(parse(&["--minutes", "-1"]).is_err());
+        assert!(parse(&["--buildings"]).is_err());
+        assert!(parse(&["--build-order"]).is_err());
+        assert!(read_ron::<Vec<BuildStep>>("missing.build_order.ron").is_err());
+    }
+}
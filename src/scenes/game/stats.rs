@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::scenes::AppState;
+
+use super::{
+    map_state::{Cargo, MapState},
+    rock::RockKind,
+    GameState,
+};
+
+const SAMPLE_INTERVAL: f32 = 1.0;
+const HISTORY: usize = 15 * 60;
+
+pub const STATS_WINDOWS: [(&str, usize); 3] =
+    [("1 min", 60), ("5 min", 5 * 60), ("15 min", HISTORY)];
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProductionStats>()
+            .add_systems(OnEnter(AppState::Game), reset)
+            .add_systems(
+                Update,
+                sample.run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            )
+            .add_observer(
+                |trigger: Trigger<RockHarvested>, mut stats: ResMut<ProductionStats>| {
+                    stats.add(Stat::Rocks(trigger.event().0), 60.0);
+                },
+            );
+    }
+}
+
+#[derive(Event)]
+pub struct RockHarvested(pub RockKind);
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stat {
+    Produced(Cargo),
+    Consumed(Cargo),
+    EnergyGenerated,
+    EnergyUsed,
+    Rocks(RockKind),
+}
+
+// Cargo and rocks are sampled per minute, energy as the average over each sample
+#[derive(Resource, Default)]
+pub struct ProductionStats {
+    history: BTreeMap<Stat, VecDeque<f32>>,
+    pending: BTreeMap<Stat, f32>,
+    elapsed: f32,
+    samples: usize,
+    generation: u64,
+}
+
+impl ProductionStats {
+    fn add(&mut self, stat: Stat, amount: f32) {
+        *self.pending.entry(stat).or_default() += amount;
+    }
+
+    fn push_sample(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        let samples = self.samples;
+        for stat in pending.keys() {
+            self.history
+                .entry(stat.clone())
+                .or_insert_with(|| VecDeque::from(vec![0.0; samples]));
+        }
+        for (stat, history) in self.history.iter_mut() {
+            history.push_back(
+                pending
+                    .get(stat)
+                    .map_or(0.0, |amount| amount / self.elapsed),
+            );
+            if history.len() > HISTORY {
+                history.pop_front();
+            }
+        }
+        self.samples = (self.samples + 1).min(HISTORY);
+        self.elapsed = 0.0;
+        self.generation += 1;
+    }
+
+    // The last `len` samples, oldest first, padded with zeros before the first sample
+    pub fn history(&self, stat: &Stat, len: usize) -> Vec<f32> {
+        let history = self.history.get(stat);
+        let available = history.map_or(0, VecDeque::len).min(len);
+        let mut samples = vec![0.0; len - available];
+        if let Some(history) = history {
+            samples.extend(history.iter().skip(history.len() - available));
+        }
+        samples
+    }
+
+    pub fn average(&self, stat: &Stat, len: usize) -> f32 {
+        let len = len.min(self.samples);
+        if len == 0 {
+            return 0.0;
+        }
+        self.history(stat, len).iter().sum::<f32>() / len as f32
+    }
+
+    // Changes every time a sample is pushed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+fn reset(mut stats: ResMut<ProductionStats>) {
+    *stats = ProductionStats::default();
+}
+
+fn sample(mut stats: ResMut<ProductionStats>, mut map_state: ResMut<MapState>, time: Res<Time>) {
+    let delta = time.delta_secs();
+    for (cargo, (produced, consumed)) in map_state.take_flow() {
+        stats.add(Stat::Produced(cargo.clone()), produced * 60.0);
+        stats.add(Stat::Consumed(cargo), consumed * 60.0);
+    }
+
+    let (available, in_use) = map_state.energy();
+    stats.add(Stat::EnergyGenerated, available * delta);
+    stats.add(Stat::EnergyUsed, in_use.min(available) * delta);
+
+    stats.elapsed += delta;
+    if stats.elapsed >= SAMPLE_INTERVAL {
+        stats.push_sample();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_is_padded_and_bounded() {
+        let mut stats = ProductionStats::default();
+        stats.elapsed = 1.0;
+        stats.add(Stat::EnergyUsed, 2.0);
+        stats.push_sample();
+        stats.elapsed = 2.0;
+        stats.add(Stat::EnergyGenerated, 4.0);
+        stats.push_sample();
+
+        assert_eq!(stats.history(&Stat::EnergyUsed, 3), vec![0.0, 2.0, 0.0]);
+        assert_eq!(
+            stats.history(&Stat::EnergyGenerated, 3),
+            vec![0.0, 0.0, 2.0]
+        );
+        assert_eq!(stats.average(&Stat::EnergyUsed, 60), 1.0);
+
+        for _ in 0..HISTORY {
+            stats.elapsed = 1.0;
+            stats.add(Stat::EnergyUsed, 1.0);
+            stats.push_sample();
+        }
+        assert_eq!(stats.samples, HISTORY);
+        assert_eq!(
+            stats.history(&Stat::EnergyUsed, HISTORY + 1)[1..],
+            [1.0; HISTORY]
+        );
+    }
+}
@@ -2,15 +2,23 @@ use bevy::prelude::*;
 use cargo_count::GameUiCargoCountPlugin;
 use container::GameUiContainerPlugin;
 use container_item::GameUiContainerItemPlugin;
+use graph::GameUiGraphPlugin;
 use header::GameUiHeaderPlugin;
 use power_bar::GameUiPowerBarPlugin;
+use room_panel::GameUiRoomPanelPlugin;
+use stats_panel::GameUiStatsPanelPlugin;
+use toast::GameUiToastPlugin;
 
 pub mod cargo_count;
 pub mod container;
 pub mod container_item;
+pub mod graph;
 pub mod header;
 pub mod palette;
 pub mod power_bar;
+pub mod room_panel;
+pub mod stats_panel;
+pub mod toast;
 
 pub struct GameUiPlugin;
 
@@ -20,6 +28,10 @@ impl Plugin for GameUiPlugin {
             .add_plugins(GameUiContainerPlugin)
             .add_plugins(GameUiContainerItemPlugin)
             .add_plugins(GameUiCargoCountPlugin)
-            .add_plugins(GameUiPowerBarPlugin);
+            .add_plugins(GameUiPowerBarPlugin)
+            .add_plugins(GameUiGraphPlugin)
+            .add_plugins(GameUiRoomPanelPlugin)
+            .add_plugins(GameUiStatsPanelPlugin)
+            .add_plugins(GameUiToastPlugin);
     }
 }
@@ -0,0 +1,136 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use crate::scenes::AppState;
+
+use super::palette::COLOR_HIGHLIGHT_DARK;
+
+pub struct GameUiGraphPlugin;
+
+impl Plugin for GameUiGraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (init, update).chain().run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+// Line graph drawn on the CPU, all series share the vertical scale
+#[derive(Component)]
+pub struct GameUiGraph {
+    pub width: u32,
+    pub height: u32,
+    pub series: Vec<(Vec<f32>, Color)>,
+}
+
+impl GameUiGraph {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            series: vec![],
+        }
+    }
+
+    pub fn max(&self) -> f32 {
+        self.series
+            .iter()
+            .flat_map(|(values, _)| values)
+            .cloned()
+            .fold(0.0, f32::max)
+    }
+}
+
+#[derive(Component)]
+enum State {
+    Idle(Handle<Image>),
+}
+
+fn init(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    graphs: Query<(Entity, &GameUiGraph), Without<State>>,
+) {
+    for (entity, graph) in graphs.iter() {
+        let image = images.add(Image::new_fill(
+            Extent3d {
+                width: graph.width,
+                height: graph.height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        ));
+        commands.entity(entity).insert((
+            Node {
+                width: Val::Px(graph.width as f32),
+                height: Val::Px(graph.height as f32),
+                flex_shrink: 0.0,
+                ..Default::default()
+            },
+            ImageNode::new(image.clone()),
+            Outline::new(Val::Px(1.0), Val::ZERO, COLOR_HIGHLIGHT_DARK),
+            State::Idle(image),
+        ));
+    }
+}
+
+fn update(
+    graphs: Query<(&GameUiGraph, &State), Changed<GameUiGraph>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (graph, state) in graphs.iter() {
+        let State::Idle(handle) = state;
+        if let Some(image) = images.get_mut(handle) {
+            draw(image, graph);
+        }
+    }
+}
+
+fn draw(image: &mut Image, graph: &GameUiGraph) {
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+    let background = Color::BLACK.with_alpha(0.5).to_srgba().to_u8_array();
+    for pixel in image.data.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&background);
+    }
+
+    let max = graph.max().max(f32::EPSILON);
+    let to_y = |value: f32| (height - 1) as f32 * (1.0 - value / max);
+
+    for (values, color) in &graph.series {
+        let color = color.to_srgba().to_u8_array();
+        let step = (width - 1) as f32 / (values.len().max(2) - 1) as f32;
+        for (i, pair) in values.windows(2).enumerate() {
+            let (x0, x1) = (i as f32 * step, (i + 1) as f32 * step);
+            let (y0, y1) = (to_y(pair[0]), to_y(pair[1]));
+            let lerp = |x: f32| {
+                let t = if x1 > x0 {
+                    ((x - x0) / (x1 - x0)).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                y0 + (y1 - y0) * t
+            };
+            // Every column the segment crosses gets a vertical span, so
+            // steep segments stay connected
+            for x in x0.round() as usize..=(x1.round() as usize).min(width - 1) {
+                let (a, b) = (lerp(x as f32 - 0.5), lerp(x as f32 + 0.5));
+                let top = (a.min(b) - 0.5).round().max(0.0) as usize;
+                let bottom = ((a.max(b) + 0.5).round() as usize).min(height - 1);
+                for y in top..=bottom {
+                    let index = (y * width + x) * 4;
+                    image.data[index..index + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{
+        input_map::{Action, InputMap},
+        sfx::{PlaySfx, Sfx},
+    },
+    scenes::{
+        game::{
+            buildings::{BuildingDef, Buildings},
+            game_cursor::{CursorLayer, GameCursor},
+            hook::Hook,
+            map_state::{MapLayer, MapNode, MapState, ProcessStatus},
+            player::PlayerState,
+        },
+        AppState,
+    },
+};
+
+use super::palette::{COLOR_CONTAINER, COLOR_HIGHLIGHT_DARK, COLOR_POWER_HIGH, COLOR_POWER_LOW};
+
+pub struct GameUiRoomPanelPlugin;
+
+impl Plugin for GameUiRoomPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (init, click, update)
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+// Inspection panel of the room selected in PlayerState::Interact
+#[derive(Component)]
+pub struct GameUiRoomPanel;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum RoomButton {
+    Enable,
+    Destroy,
+    HookMode,
+}
+
+#[derive(Component)]
+enum PanelState {
+    Idle {
+        node: Option<MapNode>,
+        thumbnail: Entity,
+        title: Entity,
+        status: Entity,
+        details: Entity,
+        buttons: Vec<(RoomButton, Entity, Entity)>,
+    },
+}
+
+fn init(
+    mut commands: Commands,
+    panels: Query<Entity, (With<GameUiRoomPanel>, Without<PanelState>)>,
+) {
+    let font = TextFont {
+        font_size: 14.0,
+        ..Default::default()
+    };
+
+    for entity in panels.iter() {
+        let mut thumbnail = Entity::PLACEHOLDER;
+        let mut title = Entity::PLACEHOLDER;
+        let mut status = Entity::PLACEHOLDER;
+        let mut details = Entity::PLACEHOLDER;
+        let mut buttons = vec![];
+        commands
+            .entity(entity)
+            .insert((
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_shrink: 0.0,
+                    column_gap: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(5.0)),
+                    align_items: AlignItems::Center,
+                    display: Display::None,
+                    ..Default::default()
+                },
+                BackgroundColor(COLOR_CONTAINER),
+            ))
+            .with_children(|parent| {
+                thumbnail = parent
+                    .spawn(Node {
+                        width: Val::Px(100.0),
+                        height: Val::Px(100.0),
+                        flex_shrink: 0.0,
+                        ..Default::default()
+                    })
+                    .id();
+                parent
+                    .spawn(Node {
+                        flex_grow: 1.0,
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        title = parent.spawn(Text::default()).id();
+                        status = parent.spawn((Text::default(), font.clone())).id();
+                        details = parent.spawn((Text::default(), font.clone())).id();
+                    });
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(5.0),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        for button in [
+                            RoomButton::Enable,
+                            RoomButton::HookMode,
+                            RoomButton::Destroy,
+                        ] {
+                            let mut label = Entity::PLACEHOLDER;
+                            let entity = parent
+                                .spawn((
+                                    Button,
+                                    button,
+                                    Node {
+                                        width: Val::Px(130.0),
+                                        height: Val::Px(26.0),
+                                        align_items: AlignItems::Center,
+                                        justify_content: JustifyContent::Center,
+                                        ..Default::default()
+                                    },
+                                    Outline::new(Val::Px(2.0), Val::ZERO, COLOR_HIGHLIGHT_DARK),
+                                    BackgroundColor(Color::BLACK),
+                                ))
+                                .with_children(|parent| {
+                                    label = parent.spawn((Text::default(), font.clone())).id();
+                                })
+                                .id();
+                            buttons.push((button, entity, label));
+                        }
+                    });
+            });
+        commands.entity(entity).insert(PanelState::Idle {
+            node: None,
+            thumbnail,
+            title,
+            status,
+            details,
+            buttons,
+        });
+    }
+}
+
+fn hook_at(x: i32, y: i32, transform: &GlobalTransform) -> bool {
+    let Vec3 { x: wx, y: wy, .. } = transform.translation();
+    GameCursor::world_to_game(wx, wy, CursorLayer::Room) == IVec2::new(x, y)
+}
+
+fn click(
+    mut commands: Commands,
+    buttons: Query<(&RoomButton, &Interaction), Changed<Interaction>>,
+    mut hooks: Query<(&mut Hook, &GlobalTransform)>,
+    mut map_state: ResMut<MapState>,
+    mut next_player_state: ResMut<NextState<PlayerState>>,
+    player_state: Res<State<PlayerState>>,
+) {
+    let PlayerState::Interact(x, y) = *player_state.get() else {
+        return;
+    };
+
+    for (button, interaction) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        commands.trigger(PlaySfx(Sfx::Click));
+        match button {
+            RoomButton::Enable => {
+                let enabled = map_state.is_enabled(x, y);
+                map_state.set_enabled(x, y, !enabled);
+            }
+            RoomButton::Destroy => {
+                map_state.remove_room(x, y, MapLayer::Main);
+                next_player_state.set(PlayerState::Idle);
+            }
+            RoomButton::HookMode => {
+                for (mut hook, transform) in hooks.iter_mut() {
+                    if hook_at(x, y, transform) {
+                        hook.0 = !hook.0;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn process_line(map_state: &MapState, x: i32, y: i32, def: &BuildingDef, index: usize) -> String {
+    let process = &def.processes[index];
+    let from = process
+        .from
+        .iter()
+        .map(|cargo| cargo.name())
+        .collect::<Vec<_>>()
+        .join(" + ");
+    let mut line = format!(
+        "\n{from} -> {}: {:.2}/s of {:.2}/s",
+        process.to.name(),
+        map_state.output_rate(x, y, index),
+        process.speed * process.ratio
+    );
+    match map_state.process_status(x, y, process) {
+        ProcessStatus::Running => {}
+        ProcessStatus::Starved(cargo) => line += &format!(" (no {})", cargo.name()),
+        ProcessStatus::OutputFull => line += " (output full)",
+    }
+    line
+}
+
+fn status(map_state: &MapState, x: i32, y: i32, def: &BuildingDef) -> (String, Color) {
+    let power = map_state.power(x, y);
+    if !map_state.is_enabled(x, y) {
+        return ("Disabled".to_string(), COLOR_POWER_LOW);
+    }
+    if def.energy_use > 0.0 && power <= 0.0 {
+        return ("No power".to_string(), COLOR_POWER_LOW);
+    }
+
+    let statuses = def
+        .processes
+        .iter()
+        .map(|process| map_state.process_status(x, y, process))
+        .collect::<Vec<_>>();
+    if !statuses.is_empty() && !statuses.contains(&ProcessStatus::Running) {
+        let starved = statuses.iter().find_map(|status| match status {
+            ProcessStatus::Starved(cargo) => Some(cargo.name()),
+            _ => None,
+        });
+        return match starved {
+            Some(cargo) => (format!("Waiting for {cargo}"), COLOR_POWER_LOW),
+            None => ("Output full".to_string(), COLOR_POWER_LOW),
+        };
+    }
+
+    if power < 1.0 {
+        return (format!("Low power: {:.0}%", power * 100.0), COLOR_POWER_LOW);
+    }
+    ("Working".to_string(), COLOR_POWER_HIGH)
+}
+
+fn update(
+    mut commands: Commands,
+    mut panels: Query<(&mut PanelState, &mut Node), With<GameUiRoomPanel>>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+    mut nodes: Query<&mut Node, Without<GameUiRoomPanel>>,
+    hooks: Query<(&Hook, &GlobalTransform)>,
+    map_state: Res<MapState>,
+    buildings: Res<Buildings>,
+    player_state: Res<State<PlayerState>>,
+    input_map: Res<InputMap>,
+    assets: Res<AssetServer>,
+) {
+    let interact = match player_state.get() {
+        PlayerState::Interact(x, y) => map_state
+            .node(*x, *y, MapLayer::Main)
+            .and_then(|node| Some((*x, *y, node.clone(), buildings.get(&node)?))),
+        _ => None,
+    };
+
+    for (mut state, mut panel_node) in panels.iter_mut() {
+        let PanelState::Idle {
+            node,
+            thumbnail,
+            title,
+            status: status_text,
+            details,
+            buttons,
+        } = &mut *state;
+
+        let Some((x, y, map_node, def)) = &interact else {
+            panel_node.display = Display::None;
+            *node = None;
+            continue;
+        };
+        let (x, y) = (*x, *y);
+        panel_node.display = Display::Flex;
+
+        if node.as_ref() != Some(map_node) {
+            *node = Some(map_node.clone());
+            if let Some(mut thumbnail) = commands.get_entity(*thumbnail) {
+                thumbnail.insert(ImageNode::new(assets.load(&def.thumbnail)));
+            }
+        }
+
+        if let Ok((mut text, _)) = texts.get_mut(*title) {
+            text.0 = def.name.clone();
+        }
+
+        if let Ok((mut text, mut color)) = texts.get_mut(*status_text) {
+            let (status, status_color) = status(&map_state, x, y, def);
+            text.0 = status;
+            color.0 = status_color;
+        }
+
+        if let Ok((mut text, _)) = texts.get_mut(*details) {
+            text.0 = if def.energy_output > 0.0 {
+                format!("Generates: {}", def.energy_output)
+            } else {
+                format!(
+                    "Power draw: {:.1} of {}",
+                    map_state.power(x, y) * def.energy_use,
+                    def.energy_use
+                )
+            };
+            text.0 += &format!(
+                "\nPriority: {} ({} to change)",
+                map_state.priority(x, y, &buildings).name(),
+                input_map.label(Action::Priority)
+            );
+            for index in 0..def.processes.len() {
+                text.0 += &process_line(&map_state, x, y, def, index);
+            }
+            let health = map_state.health(x, y);
+            if health < 1.0 {
+                text.0 += &format!(
+                    "\nHealth: {:.0}%, repair: {:.1} Silicon ({})",
+                    health * 100.0,
+                    map_state.repair_cost(x, y),
+                    input_map.label(Action::Repair)
+                );
+            }
+            if let Some(inventory) = map_state.inventory(x, y) {
+                if let Some(distance) = map_state.distance(x, y) {
+                    text.0 += &format!("\nDistance to storage: {distance}");
+                }
+                for (cargo, count) in inventory.input.iter().chain(&inventory.output) {
+                    text.0 += &format!("\n{}: {count:.1}", cargo.name());
+                }
+            }
+        }
+
+        let is_room = map_state.is_room(x, y, MapLayer::Main);
+        let hook = hooks
+            .iter()
+            .find(|(_, transform)| hook_at(x, y, transform))
+            .map(|(Hook(automatic), _)| *automatic);
+        for (button, entity, label) in buttons.iter() {
+            let (visible, name) = match button {
+                RoomButton::Enable if map_state.is_enabled(x, y) => (is_room, "Disable"),
+                RoomButton::Enable => (is_room, "Enable"),
+                RoomButton::Destroy => (is_room, "Destroy"),
+                RoomButton::HookMode => match hook {
+                    Some(true) => (true, "Hook: automatic"),
+                    Some(false) => (true, "Hook: manual"),
+                    None => (false, ""),
+                },
+            };
+            if let Ok(mut node) = nodes.get_mut(*entity) {
+                node.display = if visible {
+                    Display::Flex
+                } else {
+                    Display::None
+                };
+            }
+            if let Ok((mut text, _)) = texts.get_mut(*label) {
+                if text.0 != name {
+                    text.0 = name.to_string();
+                }
+            }
+        }
+    }
+}
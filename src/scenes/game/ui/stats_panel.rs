@@ -0,0 +1,295 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+use strum::IntoEnumIterator;
+
+use crate::{
+    components::{
+        input_map::{Action, Actions},
+        sfx::{PlaySfx, Sfx},
+    },
+    scenes::{
+        game::{
+            map_state::Cargo,
+            rock::RockKind,
+            stats::{ProductionStats, Stat, STATS_WINDOWS},
+        },
+        AppState,
+    },
+};
+
+use super::{
+    graph::GameUiGraph,
+    palette::{
+        COLOR_CONTAINER, COLOR_HIGHLIGHT_DARK, COLOR_POWER_HIGH, COLOR_POWER_LOW, COLOR_TEXT,
+    },
+};
+
+const GRAPH_WIDTH: u32 = 250;
+const GRAPH_HEIGHT: u32 = 48;
+
+pub struct GameUiStatsPanelPlugin;
+
+impl Plugin for GameUiStatsPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (init, select_window, update)
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct GameUiStatsPanel;
+
+#[derive(Component)]
+struct WindowButton(usize);
+
+struct Card {
+    graph: Entity,
+    series: Vec<(Stat, Color, String, Entity)>,
+}
+
+#[derive(Component)]
+enum State {
+    Idle { window: usize, cards: Vec<Card> },
+}
+
+fn rock_color(kind: RockKind) -> Color {
+    match kind {
+        RockKind::Silicon => Color::srgb(0.7, 0.7, 0.7),
+        RockKind::Ice => Color::srgb(0.0, 1.0, 1.0),
+        RockKind::Copper => Color::srgb(1.0, 0.6, 0.0),
+        RockKind::Uranium => Color::srgb(0.0, 1.0, 0.0),
+        RockKind::Aurelium => Color::srgb(1.0, 1.0, 0.0),
+    }
+}
+
+fn spawn_card(parent: &mut ChildBuilder, title: &str, series: Vec<(Stat, Color, String)>) -> Card {
+    let mut card = Card {
+        graph: Entity::PLACEHOLDER,
+        series: vec![],
+    };
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Text::new(title),
+                    TextColor(COLOR_TEXT),
+                    TextFont {
+                        font_size: 14.0,
+                        ..Default::default()
+                    },
+                ))
+                .with_children(|parent| {
+                    for (stat, color, label) in series {
+                        let span = parent
+                            .spawn((
+                                TextSpan::default(),
+                                TextColor(color),
+                                TextFont {
+                                    font_size: 14.0,
+                                    ..Default::default()
+                                },
+                            ))
+                            .id();
+                        card.series.push((stat, color, label, span));
+                    }
+                });
+            card.graph = parent
+                .spawn(GameUiGraph::new(GRAPH_WIDTH, GRAPH_HEIGHT))
+                .id();
+        });
+    card
+}
+
+fn init(mut commands: Commands, panels: Query<Entity, (With<GameUiStatsPanel>, Without<State>)>) {
+    for entity in panels.iter() {
+        let mut cards = vec![];
+        commands
+            .entity(entity)
+            .insert((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(100.0),
+                    left: Val::Px(400.0),
+                    right: Val::Px(340.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..Default::default()
+                },
+                BorderRadius::all(Val::Px(5.0)),
+                Outline::new(Val::Px(3.0), Val::ZERO, COLOR_HIGHLIGHT_DARK),
+                BackgroundColor(COLOR_CONTAINER),
+                FocusPolicy::Block,
+                Visibility::Hidden,
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(10.0),
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Node {
+                                flex_grow: 1.0,
+                                ..Default::default()
+                            },
+                            Text::new("Production stats"),
+                            TextColor(COLOR_TEXT),
+                        ));
+                        for (i, (name, _)) in STATS_WINDOWS.iter().enumerate() {
+                            parent
+                                .spawn((
+                                    Button,
+                                    WindowButton(i),
+                                    Node {
+                                        width: Val::Px(70.0),
+                                        height: Val::Px(26.0),
+                                        align_items: AlignItems::Center,
+                                        justify_content: JustifyContent::Center,
+                                        ..Default::default()
+                                    },
+                                    Outline::new(Val::Px(2.0), Val::ZERO, COLOR_HIGHLIGHT_DARK),
+                                    BackgroundColor(Color::BLACK),
+                                ))
+                                .with_child((
+                                    Text::new(*name),
+                                    TextFont {
+                                        font_size: 14.0,
+                                        ..Default::default()
+                                    },
+                                ));
+                        }
+                    });
+                parent
+                    .spawn(Node {
+                        flex_wrap: FlexWrap::Wrap,
+                        row_gap: Val::Px(10.0),
+                        column_gap: Val::Px(20.0),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        cards.push(spawn_card(
+                            parent,
+                            "Energy",
+                            vec![
+                                (
+                                    Stat::EnergyGenerated,
+                                    COLOR_POWER_HIGH,
+                                    " generated ".to_string(),
+                                ),
+                                (Stat::EnergyUsed, COLOR_POWER_LOW, " used ".to_string()),
+                            ],
+                        ));
+                        cards.push(spawn_card(
+                            parent,
+                            "Rocks per min",
+                            RockKind::iter()
+                                .map(|kind| {
+                                    (
+                                        Stat::Rocks(kind),
+                                        rock_color(kind),
+                                        format!(" {} ", kind.name()),
+                                    )
+                                })
+                                .collect(),
+                        ));
+                        for cargo in Cargo::iter() {
+                            cards.push(spawn_card(
+                                parent,
+                                &format!("{} per min", cargo.name()),
+                                vec![
+                                    (
+                                        Stat::Produced(cargo.clone()),
+                                        COLOR_POWER_HIGH,
+                                        " +".to_string(),
+                                    ),
+                                    (Stat::Consumed(cargo), COLOR_POWER_LOW, " -".to_string()),
+                                ],
+                            ));
+                        }
+                    });
+            });
+        commands
+            .entity(entity)
+            .insert(State::Idle { window: 0, cards });
+    }
+}
+
+fn select_window(
+    mut commands: Commands,
+    buttons: Query<(&WindowButton, &Interaction), Changed<Interaction>>,
+    mut panels: Query<&mut State, With<GameUiStatsPanel>>,
+) {
+    for (WindowButton(i), interaction) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        commands.trigger(PlaySfx(Sfx::Click));
+        for mut state in panels.iter_mut() {
+            let State::Idle { window, .. } = &mut *state;
+            *window = *i;
+        }
+    }
+}
+
+fn update(
+    mut panels: Query<(Ref<State>, &mut Visibility), With<GameUiStatsPanel>>,
+    mut buttons: Query<(&WindowButton, &mut BackgroundColor)>,
+    mut graphs: Query<&mut GameUiGraph>,
+    mut spans: Query<&mut TextSpan>,
+    stats: Res<ProductionStats>,
+    actions: Actions,
+    mut drawn: Local<Option<u64>>,
+) {
+    for (state, mut visibility) in panels.iter_mut() {
+        if actions.just_pressed(Action::Stats) {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+        if *visibility == Visibility::Hidden
+            || !(*drawn != Some(stats.generation())
+                || state.is_changed()
+                || visibility.is_changed())
+        {
+            continue;
+        }
+        *drawn = Some(stats.generation());
+
+        let State::Idle { window, cards } = &*state;
+        for (WindowButton(i), mut color) in buttons.iter_mut() {
+            color.0 = if i == window {
+                COLOR_HIGHLIGHT_DARK
+            } else {
+                Color::BLACK
+            };
+        }
+
+        let (_, len) = STATS_WINDOWS[*window];
+        for card in cards {
+            if let Ok(mut graph) = graphs.get_mut(card.graph) {
+                graph.series = card
+                    .series
+                    .iter()
+                    .map(|(stat, color, _, _)| (stats.history(stat, len), *color))
+                    .collect();
+            }
+            for (stat, _, label, span) in &card.series {
+                if let Ok(mut span) = spans.get_mut(*span) {
+                    span.0 = format!("{label}{:.1}", stats.average(stat, len));
+                }
+            }
+        }
+    }
+}
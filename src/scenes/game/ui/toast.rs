@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use crate::scenes::AppState;
+
+use super::palette::{COLOR_CONTAINER, COLOR_HIGHLIGHT_DARK, COLOR_TEXT};
+
+const TOAST_TIME: f32 = 4.0;
+const TOAST_FADE: f32 = 0.5;
+
+pub struct GameUiToastPlugin;
+
+impl Plugin for GameUiToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (init, update).chain().run_if(in_state(AppState::Game)),
+        )
+        .add_observer(show);
+    }
+}
+
+#[derive(Component)]
+pub struct GameUiToast;
+
+#[derive(Event)]
+pub struct ShowToast(pub String);
+
+#[derive(Component)]
+enum State {
+    Idle { text: Entity, timer: f32 },
+}
+
+fn init(mut commands: Commands, toasts: Query<Entity, (With<GameUiToast>, Without<State>)>) {
+    for entity in toasts.iter() {
+        let text = commands
+            .spawn((Text::default(), TextColor(COLOR_TEXT.with_alpha(0.0))))
+            .id();
+        commands
+            .entity(entity)
+            .insert((
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.0),
+                    left: Val::Percent(50.0),
+                    width: Val::Px(400.0),
+                    margin: UiRect::left(Val::Px(-200.0)),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                BorderRadius::all(Val::Px(5.0)),
+                Outline::new(Val::Px(3.0), Val::ZERO, COLOR_HIGHLIGHT_DARK),
+                BackgroundColor(COLOR_CONTAINER),
+                Visibility::Hidden,
+                State::Idle { text, timer: 0.0 },
+            ))
+            .add_child(text);
+    }
+}
+
+fn show(
+    trigger: Trigger<ShowToast>,
+    mut toasts: Query<&mut State, With<GameUiToast>>,
+    mut texts: Query<&mut Text>,
+) {
+    for mut state in toasts.iter_mut() {
+        let State::Idle { text, timer } = &mut *state;
+        *timer = TOAST_TIME;
+        if let Ok(mut text) = texts.get_mut(*text) {
+            text.0 = trigger.event().0.clone();
+        }
+    }
+}
+
+fn update(
+    mut toasts: Query<(&mut State, &mut Visibility, &mut BackgroundColor), With<GameUiToast>>,
+    mut colors: Query<&mut TextColor>,
+    time: Res<Time>,
+) {
+    for (mut state, mut visibility, mut background) in toasts.iter_mut() {
+        let State::Idle { text, timer } = &mut *state;
+        if *timer <= 0.0 {
+            continue;
+        }
+        *timer = (*timer - time.delta_secs()).max(0.0);
+
+        let alpha = (*timer / TOAST_FADE)
+            .min((TOAST_TIME - *timer) / TOAST_FADE)
+            .clamp(0.0, 1.0);
+        *visibility = if *timer > 0.0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        background.0 = COLOR_CONTAINER.with_alpha(COLOR_CONTAINER.alpha() * alpha);
+        if let Ok(mut color) = colors.get_mut(*text) {
+            color.0 = COLOR_TEXT.with_alpha(alpha);
+        }
+    }
+}
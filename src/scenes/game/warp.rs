@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scenes::{AppSceneRoot, AppState};
+
+use super::{
+    map_state::{Cargo, MapState},
+    GameState,
+};
+
+pub const WARP_BATTERIES: f32 = 5.0;
+pub const WARP_ENERGY_RATIO: f32 = 0.25;
+pub const WARP_STABLE_TIME: f32 = 30.0;
+const WARP_CINEMATIC_TIME: f32 = 4.0;
+
+pub struct WarpPlugin;
+
+impl Plugin for WarpPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WarpState::default())
+            .add_systems(
+                Update,
+                charge.run_if(in_state(AppState::Game).and(in_state(GameState::Idle))),
+            )
+            .add_systems(OnEnter(GameState::Warp), start_cinematic)
+            .add_systems(
+                Update,
+                cinematic.run_if(in_state(AppState::Game).and(in_state(GameState::Warp))),
+            );
+    }
+}
+
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct WarpState {
+    pub charge: f32,
+    pub stable: f32,
+}
+
+#[derive(Component)]
+struct WarpFlash {
+    started: f32,
+}
+
+fn charge(
+    mut warp_state: ResMut<WarpState>,
+    mut map_state: ResMut<MapState>,
+    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time>,
+) {
+    let (batteries, _) = map_state.cargo_count(Cargo::Batteries);
+    let take = batteries.min(WARP_BATTERIES - warp_state.charge);
+    if take > 0.0 {
+        map_state.harvest(Cargo::Batteries, -take);
+        warp_state.charge += take;
+    }
+
+    if warp_state.charge >= WARP_BATTERIES && map_state.energy_ratio() >= WARP_ENERGY_RATIO {
+        warp_state.stable += time.delta_secs();
+    } else {
+        warp_state.stable = 0.0;
+    }
+
+    if warp_state.stable >= WARP_STABLE_TIME {
+        next_state.set(GameState::Warp);
+    }
+}
+
+fn start_cinematic(mut commands: Commands, root_entity: Res<AppSceneRoot>, time: Res<Time>) {
+    commands.entity(root_entity.ui).with_child((
+        WarpFlash {
+            started: time.elapsed_secs(),
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..Default::default()
+        },
+        BackgroundColor(Color::WHITE.with_alpha(0.0)),
+        ZIndex(i32::MAX),
+    ));
+}
+
+fn cinematic(
+    mut flashes: Query<(&WarpFlash, &mut BackgroundColor)>,
+    mut camera: Query<&mut Transform, With<Camera3d>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    time: Res<Time>,
+) {
+    for (flash, mut color) in flashes.iter_mut() {
+        let t = ((time.elapsed_secs() - flash.started) / WARP_CINEMATIC_TIME).min(1.0);
+
+        if let Ok(mut camera) = camera.get_single_mut() {
+            camera.translation.x -= 200.0 * t * t * t * time.delta_secs();
+        }
+
+        color.0.set_alpha(t * t);
+
+        if t >= 1.0 {
+            next_state.set(AppState::Titles);
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use bevy::{
     audio::PlaybackMode,
     core_pipeline::{bloom::Bloom, tonemapping::DebandDither},
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
     prelude::*,
 };
 
@@ -8,25 +12,59 @@ use crate::components::{
     background::{BackgroundPluginSettings, RenderBackground},
     game_button::GameButton,
     mouse_event::Clicked,
+    sfx::AudioChannel,
 };
 
-use super::{AppSceneRoot, AppState};
+use super::{
+    game::{
+        save::{PendingLoad, SaveData},
+        seed::GameSeed,
+        LogisticsMode,
+    },
+    AppSceneRoot, AppState,
+};
+
+pub const STARS_SHADER: &str = "stars_main_menu.wgsl";
 
 pub struct MainMenuPlugin;
 
 pub mod about;
+pub mod controls;
+pub mod load;
 pub mod settings;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(AppState::MainMenu), setup)
-            .insert_resource(BackgroundPluginSettings {
-                shader: "stars_main_menu.wgsl".to_string(),
-            });
+            .add_systems(
+                Update,
+                (edit_seed, update_seed, update_logistics).run_if(in_state(AppState::MainMenu)),
+            );
     }
 }
 
-fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>, asset_server: Res<AssetServer>) {
+#[derive(Component)]
+struct SeedText;
+
+#[derive(Component)]
+struct LogisticsText;
+
+fn logistics_text(logistics: LogisticsMode) -> String {
+    format!("Logistics: {}", if logistics.0 { "On" } else { "Off" })
+}
+
+fn setup(
+    mut commands: Commands,
+    root_entity: Res<AppSceneRoot>,
+    asset_server: Res<AssetServer>,
+    seed: Res<GameSeed>,
+    logistics: Res<LogisticsMode>,
+) {
+    commands.insert_resource(BackgroundPluginSettings {
+        shader: STARS_SHADER.to_string(),
+        seed: seed.background(),
+    });
+
     commands
         .entity(root_entity.world)
         .with_child((
@@ -42,6 +80,7 @@ fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>, asset_server: R
             DebandDither::Enabled,
         ))
         .with_child((
+            AudioChannel::Music,
             AudioPlayer::new(asset_server.load("Cojam - Milky Main Menu.ogg")),
             PlaybackSettings {
                 mode: PlaybackMode::Loop,
@@ -53,12 +92,49 @@ fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>, asset_server: R
         height: Val::Px(5.0),
         ..Default::default()
     };
+    let continue_game = SaveData::latest().is_some().then(|| {
+        commands
+            .spawn(GameButton::new("Continue", 160.0))
+            .observe(
+                |_: Trigger<Clicked>,
+                 mut commands: Commands,
+                 mut next: ResMut<NextState<AppState>>| {
+                    if let Some(save) = SaveData::latest() {
+                        commands.insert_resource(PendingLoad(save));
+                        next.set(AppState::Game);
+                    }
+                },
+            )
+            .id()
+    });
     let new_game = commands
         .spawn(GameButton::new("New game", 200.0))
         .observe(
             |_: Trigger<Clicked>, mut next: ResMut<NextState<AppState>>| next.set(AppState::Game),
         )
         .id();
+    let random_seed = commands
+        .spawn(GameButton::new("Random seed", 280.0))
+        .observe(|_: Trigger<Clicked>, mut seed: ResMut<GameSeed>| {
+            *seed = GameSeed::random();
+        })
+        .id();
+    let toggle_logistics = commands
+        .spawn(GameButton::new("Logistics", 160.0))
+        .observe(
+            |_: Trigger<Clicked>, mut logistics: ResMut<LogisticsMode>| {
+                logistics.0 = !logistics.0;
+            },
+        )
+        .id();
+    let load = commands
+        .spawn(GameButton::new("Load", 200.0))
+        .observe(
+            |_: Trigger<Clicked>, mut next: ResMut<NextState<AppState>>| {
+                next.set(AppState::MainMenuLoad)
+            },
+        )
+        .id();
     let settings = commands
         .spawn(GameButton::new("Settings", 200.0))
         .observe(
@@ -94,10 +170,67 @@ fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>, asset_server: R
             justify_content: JustifyContent::Center,
             ..Default::default()
         })
+        .add_children(continue_game.as_slice())
         .add_children(&[new_game])
+        .with_child((
+            SeedText,
+            Text::new(format!("Seed: {}\nType digits to change", seed.0)),
+            TextLayout::new_with_justify(JustifyText::Center),
+        ))
+        .add_children(&[random_seed])
+        .with_child((
+            LogisticsText,
+            Text::new(logistics_text(*logistics)),
+            TextLayout::new_with_justify(JustifyText::Center),
+        ))
+        .add_children(&[toggle_logistics])
+        .with_child(spacer.clone())
+        .add_children(&[load])
         .with_child(spacer.clone())
         .add_children(&[settings])
         .with_child(spacer.clone())
         .add_children(&[exit]);
     });
 }
+
+fn edit_seed(mut keyboard: EventReader<KeyboardInput>, mut seed: ResMut<GameSeed>) {
+    for event in keyboard.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        let value = match &event.logical_key {
+            Key::Character(c) => match c.parse::<u64>() {
+                Ok(digit) if c.len() == 1 => {
+                    seed.0.checked_mul(10).and_then(|s| s.checked_add(digit))
+                }
+                _ => None,
+            },
+            Key::Backspace => Some(seed.0 / 10),
+            _ => None,
+        };
+        if let Some(value) = value {
+            seed.0 = value;
+        }
+    }
+}
+
+fn update_seed(seed: Res<GameSeed>, mut texts: Query<&mut Text, With<SeedText>>) {
+    if !seed.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.0 = format!("Seed: {}\nType digits to change", seed.0);
+    }
+}
+
+fn update_logistics(
+    logistics: Res<LogisticsMode>,
+    mut texts: Query<&mut Text, With<LogisticsText>>,
+) {
+    if !logistics.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.0 = logistics_text(*logistics);
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::{
+    core_pipeline::{bloom::Bloom, tonemapping::DebandDither},
+    prelude::*,
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    components::{
+        background::RenderBackground,
+        game_button::GameButton,
+        input_map::{Action, Binding, InputMap},
+        mouse_event::Clicked,
+    },
+    scenes::game::ui::palette::{COLOR_HIGHLIGHT_DARK, COLOR_POWER_HIGH},
+};
+
+use super::{settings::SettingsSaver, AppSceneRoot, AppState};
+
+pub struct MainMenuControlsPlugin;
+
+impl Plugin for MainMenuControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MainMenuControls), setup)
+            .add_systems(
+                OnExit(AppState::MainMenuControls),
+                |mut commands: Commands| commands.remove_resource::<Rebinding>(),
+            )
+            .add_systems(
+                Update,
+                (select, capture, update)
+                    .chain()
+                    .run_if(in_state(AppState::MainMenuControls)),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Copy)]
+struct Rebinding(Action);
+
+#[derive(Component)]
+struct BindingButton(Action);
+
+#[derive(Component)]
+struct BindingSlot(Action);
+
+fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>) {
+    commands.entity(root_entity.world).with_child((
+        Camera3d::default(),
+        Camera {
+            hdr: true,
+            clear_color: ClearColorConfig::None,
+            ..Default::default()
+        },
+        RenderBackground,
+        Msaa::Off,
+        Bloom::NATURAL,
+        DebandDither::Enabled,
+    ));
+    let spacer = Node {
+        height: Val::Px(5.0),
+        ..Default::default()
+    };
+    let reset = commands
+        .spawn(GameButton::new("Reset", 0.0))
+        .observe(
+            |_: Trigger<Clicked>, mut commands: Commands, mut input_map: ResMut<InputMap>| {
+                commands.remove_resource::<Rebinding>();
+                input_map.reset();
+            },
+        )
+        .id();
+    let back = commands
+        .spawn(GameButton::new("Back", 200.0))
+        .observe(
+            |_: Trigger<Clicked>,
+             mut saver: SettingsSaver,
+             mut next: ResMut<NextState<AppState>>| {
+                saver.save();
+                next.set(AppState::MainMenuSettings)
+            },
+        )
+        .id();
+
+    commands.entity(root_entity.ui).with_children(|root| {
+        root.spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(15.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    width: Val::Px(800.0),
+                    flex_wrap: FlexWrap::Wrap,
+                    row_gap: Val::Px(8.0),
+                    column_gap: Val::Px(20.0),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for action in Action::iter() {
+                        parent
+                            .spawn(Node {
+                                width: Val::Px(390.0),
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(10.0),
+                                ..Default::default()
+                            })
+                            .with_child((
+                                Node {
+                                    width: Val::Px(170.0),
+                                    flex_shrink: 0.0,
+                                    ..Default::default()
+                                },
+                                Text::new(action.name()),
+                            ))
+                            .with_children(|parent| {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        Node {
+                                            width: Val::Percent(100.0),
+                                            height: Val::Px(30.0),
+                                            align_items: AlignItems::Center,
+                                            justify_content: JustifyContent::Center,
+                                            ..Default::default()
+                                        },
+                                        Outline {
+                                            color: COLOR_HIGHLIGHT_DARK,
+                                            offset: Val::ZERO,
+                                            width: Val::Px(3.0),
+                                        },
+                                        BackgroundColor(Color::BLACK),
+                                        BindingButton(action),
+                                    ))
+                                    .with_child((BindingSlot(action), Text::default()));
+                            });
+                    }
+                });
+            parent
+                .spawn(Node {
+                    width: Val::Px(800.0),
+                    align_items: AlignItems::End,
+                    justify_content: JustifyContent::End,
+                    column_gap: Val::Px(10.0),
+                    ..Default::default()
+                })
+                .with_child(spacer.clone())
+                .add_children(&[reset, back]);
+        });
+    });
+}
+
+fn select(
+    mut commands: Commands,
+    buttons: Query<(&BindingButton, &Interaction), Changed<Interaction>>,
+) {
+    for (BindingButton(action), interaction) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            commands.insert_resource(Rebinding(*action));
+        }
+    }
+}
+
+fn capture(
+    mut commands: Commands,
+    rebinding: Option<Res<Rebinding>>,
+    mut input_map: ResMut<InputMap>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+) {
+    let Some(rebinding) = rebinding else {
+        return;
+    };
+
+    // The click that selected the slot must not bind itself
+    if rebinding.is_added() {
+        return;
+    }
+    let Rebinding(action) = *rebinding;
+
+    let binding = keyboard
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        });
+
+    if let Some(binding) = binding {
+        input_map.bind(action, binding);
+        commands.remove_resource::<Rebinding>();
+    }
+}
+
+fn update(
+    input_map: Res<InputMap>,
+    rebinding: Option<Res<Rebinding>>,
+    mut slots: Query<(&BindingSlot, &mut Text, &mut TextColor)>,
+) {
+    let rebinding = rebinding.map(|r| r.0);
+    for (BindingSlot(action), mut text, mut color) in slots.iter_mut() {
+        let value = if rebinding == Some(*action) {
+            "Press a key...".to_string()
+        } else {
+            input_map.label(*action)
+        };
+        if text.0 != value {
+            text.0 = value;
+        }
+        color.0 = if rebinding == Some(*action) {
+            COLOR_POWER_HIGH
+        } else {
+            Color::WHITE
+        };
+    }
+}
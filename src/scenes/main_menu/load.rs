@@ -0,0 +1,122 @@
+use bevy::{
+    core_pipeline::{bloom::Bloom, tonemapping::DebandDither},
+    prelude::*,
+};
+
+use crate::{
+    components::{background::RenderBackground, game_button::GameButton, mouse_event::Clicked},
+    scenes::game::{
+        save::{PendingLoad, SaveData, SAVE_SLOTS, SAVE_SLOT_NAMES},
+        ui::palette::{COLOR_CONTAINER, COLOR_HIGHLIGHT_DARK},
+    },
+};
+
+use super::{AppSceneRoot, AppState};
+
+pub struct MainMenuLoadPlugin;
+
+impl Plugin for MainMenuLoadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MainMenuLoad), setup);
+    }
+}
+
+fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>) {
+    commands.entity(root_entity.world).with_child((
+        Camera3d::default(),
+        Camera {
+            hdr: true,
+            clear_color: ClearColorConfig::None,
+            ..Default::default()
+        },
+        RenderBackground,
+        Msaa::Off,
+        Bloom::NATURAL,
+        DebandDither::Enabled,
+    ));
+    let spacer = Node {
+        height: Val::Px(5.0),
+        ..Default::default()
+    };
+    let back = commands
+        .spawn(GameButton::new("Back", 200.0))
+        .observe(
+            |_: Trigger<Clicked>, mut next: ResMut<NextState<AppState>>| {
+                next.set(AppState::MainMenu)
+            },
+        )
+        .id();
+    commands.entity(root_entity.ui).with_children(|root| {
+        root.spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(15.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for slot in 0..SAVE_SLOTS {
+                let save = SaveData::read(slot);
+                parent
+                    .spawn((
+                        Node {
+                            width: Val::Px(800.0),
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(20.0),
+                            padding: UiRect::all(Val::Px(10.0)),
+                            ..Default::default()
+                        },
+                        Outline {
+                            color: COLOR_HIGHLIGHT_DARK,
+                            offset: Val::ZERO,
+                            width: Val::Px(3.0),
+                        },
+                        BackgroundColor(COLOR_CONTAINER),
+                    ))
+                    .with_children(|parent| match save.as_ref() {
+                        Some(save) => save.spawn_thumbnail(parent, 100.0),
+                        None => {
+                            parent.spawn((
+                                Node {
+                                    width: Val::Px(100.0),
+                                    height: Val::Px(100.0),
+                                    flex_shrink: 0.0,
+                                    ..Default::default()
+                                },
+                                BackgroundColor(Color::BLACK),
+                            ));
+                        }
+                    })
+                    .with_child((
+                        Node {
+                            width: Val::Percent(100.0),
+                            ..Default::default()
+                        },
+                        Text::new(match save.as_ref() {
+                            Some(save) => format!("{}\n{}", SAVE_SLOT_NAMES[slot], save.title()),
+                            None => format!("{}\nEmpty", SAVE_SLOT_NAMES[slot]),
+                        }),
+                    ))
+                    .with_children(|parent| {
+                        if save.is_none() {
+                            return;
+                        }
+                        parent.spawn(GameButton::new("Load", 200.0)).observe(
+                            move |_: Trigger<Clicked>,
+                                  mut commands: Commands,
+                                  mut next: ResMut<NextState<AppState>>| {
+                                if let Some(save) = SaveData::read(slot) {
+                                    commands.insert_resource(PendingLoad(save));
+                                    next.set(AppState::Game);
+                                }
+                            },
+                        );
+                    });
+            }
+        })
+        .with_child(spacer.clone())
+        .add_children(&[back]);
+    });
+}
@@ -1,10 +1,17 @@
+use std::path::PathBuf;
+
 use bevy::{
     core_pipeline::{bloom::Bloom, tonemapping::DebandDither},
+    ecs::system::SystemParam,
     prelude::*,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    components::{background::RenderBackground, game_button::GameButton, mouse_event::Clicked},
+    components::{
+        background::RenderBackground, game_button::GameButton, input_map::InputMap,
+        mouse_event::Clicked,
+    },
     scenes::game::ui::palette::{COLOR_HIGHLIGHT_DARK, COLOR_POWER_HIGH, COLOR_POWER_LOW},
 };
 
@@ -14,19 +21,18 @@ pub struct MainMenuSettingsPlugin;
 
 impl Plugin for MainMenuSettingsPlugin {
     fn build(&self, app: &mut App) {
+        let SettingsFile { settings, bindings } = SettingsFile::read();
+        let args = SettingsArgs::parse(&std::env::args().collect::<Vec<_>>());
         app.add_systems(OnEnter(AppState::MainMenuSettings), setup)
             .add_systems(Update, update.run_if(in_state(AppState::MainMenuSettings)))
-            .insert_resource(GameSettings {
-                music_volume: 0.2,
-                effects_volume: 0.2,
-                oit_layers: 4,
-                bloom: true,
-                fullscreen: false,
-            });
+            .insert_resource(args.apply(settings.clone()))
+            .insert_resource(StoredSettings(settings))
+            .insert_resource(args)
+            .insert_resource(bindings);
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Serialize)]
 pub struct GameSettings {
     pub music_volume: f32,
     pub effects_volume: f32,
@@ -35,6 +41,193 @@ pub struct GameSettings {
     pub fullscreen: bool,
 }
 
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.2,
+            effects_volume: 0.2,
+            oit_layers: 4,
+            bloom: true,
+            fullscreen: false,
+        }
+    }
+}
+
+impl GameSettings {
+    // Every field is parsed on its own, so one bad value doesn't reset the rest
+    fn from_value(value: ron::Value) -> Self {
+        let mut settings = Self::default();
+        let ron::Value::Map(fields) = value else {
+            warn!("Settings are not a struct, using defaults");
+            return settings;
+        };
+        for (key, value) in fields.iter() {
+            let ron::Value::String(key) = key else {
+                continue;
+            };
+            let value = value.clone();
+            let parsed = match key.as_str() {
+                "music_volume" => value.into_rust().map(|v| settings.music_volume = v),
+                "effects_volume" => value.into_rust().map(|v| settings.effects_volume = v),
+                "oit_layers" => value.into_rust().map(|v| settings.oit_layers = v),
+                "bloom" => value.into_rust().map(|v| settings.bloom = v),
+                "fullscreen" => value.into_rust().map(|v| settings.fullscreen = v),
+                _ => Ok(()),
+            };
+            if let Err(err) = parsed {
+                warn!("Ignoring setting {key}: {err}");
+            }
+        }
+        settings.validated()
+    }
+
+    fn validated(self) -> Self {
+        let default = Self::default();
+        let volume = |v: f32, default: f32| {
+            if v.is_finite() {
+                v.clamp(0.0, 1.0)
+            } else {
+                default
+            }
+        };
+        Self {
+            music_volume: volume(self.music_volume, default.music_volume),
+            effects_volume: volume(self.effects_volume, default.effects_volume),
+            oit_layers: self.oit_layers.clamp(1, 16),
+            ..self
+        }
+    }
+}
+
+// Launch options override the settings file for this run only
+#[derive(Resource, Default, Clone, Copy)]
+pub struct SettingsArgs {
+    fullscreen: Option<bool>,
+    oit_layers: Option<u32>,
+}
+
+impl SettingsArgs {
+    fn parse(args: &[String]) -> Self {
+        let fullscreen = if args.iter().any(|arg| arg == "--windowed") {
+            Some(false)
+        } else if args.iter().any(|arg| arg == "--fullscreen") {
+            Some(true)
+        } else {
+            None
+        };
+        let oit_layers = args
+            .iter()
+            .skip_while(|arg| *arg != "--oit-layers")
+            .nth(1)
+            .and_then(|layers| layers.parse().ok());
+        Self {
+            fullscreen,
+            oit_layers,
+        }
+    }
+
+    fn apply(&self, settings: GameSettings) -> GameSettings {
+        GameSettings {
+            fullscreen: self.fullscreen.unwrap_or(settings.fullscreen),
+            oit_layers: self.oit_layers.unwrap_or(settings.oit_layers),
+            ..settings
+        }
+        .validated()
+    }
+
+    // Values still at their launch override weren't touched in the menu, so the
+    // file keeps what it had for them
+    fn persisted(&self, current: &GameSettings, stored: &GameSettings) -> GameSettings {
+        let launched = self.apply(stored.clone());
+        let mut settings = current.clone();
+        if self.fullscreen.is_some() && settings.fullscreen == launched.fullscreen {
+            settings.fullscreen = stored.fullscreen;
+        }
+        if self.oit_layers.is_some() && settings.oit_layers == launched.oit_layers {
+            settings.oit_layers = stored.oit_layers;
+        }
+        settings
+    }
+}
+
+// Settings as last read from or written to the settings file
+#[derive(Resource)]
+pub struct StoredSettings(GameSettings);
+
+#[derive(SystemParam)]
+pub struct SettingsSaver<'w> {
+    settings: Res<'w, GameSettings>,
+    bindings: Res<'w, InputMap>,
+    args: Res<'w, SettingsArgs>,
+    stored: ResMut<'w, StoredSettings>,
+}
+
+impl SettingsSaver<'_> {
+    pub fn save(&mut self) {
+        let file = SettingsFile {
+            settings: self.args.persisted(&self.settings, &self.stored.0),
+            bindings: self.bindings.clone(),
+        };
+        if let Err(err) = file.write() {
+            warn!("Failed to write settings: {err}");
+        }
+        self.stored.0 = file.settings;
+    }
+}
+
+#[derive(Default, Serialize)]
+pub struct SettingsFile {
+    pub settings: GameSettings,
+    pub bindings: InputMap,
+}
+
+#[derive(Deserialize)]
+struct RawSettingsFile {
+    #[serde(default = "empty_value")]
+    settings: ron::Value,
+    #[serde(default)]
+    bindings: InputMap,
+}
+
+fn empty_value() -> ron::Value {
+    ron::Value::Map(Default::default())
+}
+
+impl SettingsFile {
+    pub fn read() -> Self {
+        let Ok(text) = std::fs::read_to_string(settings_path()) else {
+            return Self::default();
+        };
+        match ron::from_str::<RawSettingsFile>(&text) {
+            Ok(raw) => Self {
+                settings: GameSettings::from_value(raw.settings),
+                bindings: raw.bindings.validated(),
+            },
+            Err(err) => {
+                warn!("Failed to parse settings: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn write(&self) -> Result<(), String> {
+        let path = settings_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let text =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+}
+
+fn settings_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("avaruus")
+        .join("settings.ron")
+}
+
 #[derive(Resource)]
 struct Entities {
     music_bar: Entity,
@@ -64,11 +257,22 @@ fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>) {
     let apply = commands
         .spawn(GameButton::new("Apply", 200.0))
         .observe(
-            |_: Trigger<Clicked>, mut next: ResMut<NextState<AppState>>| {
+            |_: Trigger<Clicked>,
+             mut saver: SettingsSaver,
+             mut next: ResMut<NextState<AppState>>| {
+                saver.save();
                 next.set(AppState::MainMenu)
             },
         )
         .id();
+    let controls = commands
+        .spawn(GameButton::new("Controls", 60.0))
+        .observe(
+            |_: Trigger<Clicked>, mut next: ResMut<NextState<AppState>>| {
+                next.set(AppState::MainMenuControls)
+            },
+        )
+        .id();
 
     let music_bar = commands
         .spawn((
@@ -167,7 +371,6 @@ fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>) {
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
                     column_gap: Val::Px(10.0),
-                    display: Display::None,
                     ..Default::default()
                 })
                 .with_child((
@@ -371,7 +574,7 @@ fn setup(mut commands: Commands, root_entity: Res<AppSceneRoot>) {
                     ..Default::default()
                 })
                 .with_child(spacer.clone())
-                .add_children(&[apply]);
+                .add_children(&[controls, apply]);
         });
     });
 }
@@ -408,3 +611,68 @@ fn update(
         text.0 = if settings.fullscreen { "ON" } else { "OFF" }.to_string();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::components::input_map::{Action, Binding};
+
+    use super::*;
+
+    fn parse(text: &str) -> SettingsFile {
+        let raw = ron::from_str::<RawSettingsFile>(text).unwrap();
+        SettingsFile {
+            settings: GameSettings::from_value(raw.settings),
+            bindings: raw.bindings.validated(),
+        }
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let mut file = SettingsFile::default();
+        file.settings.music_volume = 0.7;
+        file.settings.fullscreen = true;
+        file.bindings
+            .bind(Action::Pause, Binding::Key(KeyCode::KeyQ));
+        let text = ron::ser::to_string_pretty(&file, Default::default()).unwrap();
+
+        let read = parse(&text);
+        assert_eq!(read.settings.music_volume, 0.7);
+        assert!(read.settings.fullscreen);
+        assert_eq!(
+            read.bindings.bindings(Action::Pause),
+            &[Binding::Key(KeyCode::KeyQ)]
+        );
+    }
+
+    #[test]
+    fn bad_settings_fall_back_to_defaults() {
+        let read = parse("(settings: (music_volume: \"loud\", oit_layers: 99, bloom: false))");
+        assert_eq!(
+            read.settings.music_volume,
+            GameSettings::default().music_volume
+        );
+        assert_eq!(read.settings.oit_layers, 16);
+        assert!(!read.settings.bloom);
+        assert_eq!(read.bindings.label(Action::PanUp), "W / Up");
+    }
+
+    #[test]
+    fn launch_options_are_not_persisted() {
+        let args = ["--fullscreen", "--oit-layers", "8"].map(String::from);
+        let args = SettingsArgs::parse(&args);
+        let stored = GameSettings::default();
+
+        let mut current = args.apply(stored.clone());
+        assert!(current.fullscreen);
+        assert_eq!(current.oit_layers, 8);
+
+        current.music_volume = 0.5;
+        let persisted = args.persisted(&current, &stored);
+        assert_eq!(persisted.music_volume, 0.5);
+        assert!(!persisted.fullscreen);
+        assert_eq!(persisted.oit_layers, stored.oit_layers);
+
+        current.oit_layers = 2;
+        assert_eq!(args.persisted(&current, &stored).oit_layers, 2);
+    }
+}
@@ -0,0 +1,264 @@
+use bevy::{
+    core_pipeline::{bloom::Bloom, tonemapping::DebandDither},
+    gltf::Gltf,
+    prelude::*,
+};
+
+use crate::{
+    components::{
+        background::{BackgroundPluginSettings, RenderBackground},
+        mipmaps::{GenerateMips, GenerateMipsMode},
+    },
+    scenes::game::ui::palette::{COLOR_HIGHLIGHT_DARK, COLOR_POWER_HIGH, COLOR_POWER_LOW},
+};
+
+use super::{
+    game::{models, seed::GameSeed, shaders, BuildingDefs, DEFAULT_BUILDINGS},
+    main_menu::STARS_SHADER,
+    AppSceneRoot, AppState,
+};
+
+const MAIN_MENU_MUSIC: &str = "Cojam - Milky Main Menu.ogg";
+
+pub struct SplashPlugin;
+
+impl Plugin for SplashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Splash), setup)
+            .add_systems(Update, update.run_if(in_state(AppState::Splash)));
+    }
+}
+
+struct Preload {
+    path: String,
+    handle: UntypedHandle,
+    materials: &'static [&'static str],
+    optional: bool,
+    done: bool,
+}
+
+// Handles stay here after the splash, so preloaded assets are never unloaded
+#[derive(Resource, Default)]
+pub struct PreloadedAssets {
+    assets: Vec<Preload>,
+    buildings: Handle<BuildingDefs>,
+    thumbnails: bool,
+    errors: Vec<String>,
+}
+
+impl PreloadedAssets {
+    fn add(&mut self, path: &str, handle: UntypedHandle) -> &mut Preload {
+        self.assets.push(Preload {
+            path: path.to_string(),
+            handle,
+            materials: &[],
+            optional: false,
+            done: false,
+        });
+        self.assets.last_mut().unwrap()
+    }
+}
+
+#[derive(Resource)]
+struct Entities {
+    bar: Entity,
+    status: Entity,
+}
+
+fn setup(
+    mut commands: Commands,
+    root_entity: Res<AppSceneRoot>,
+    asset_server: Res<AssetServer>,
+    seed: Res<GameSeed>,
+) {
+    commands.insert_resource(BackgroundPluginSettings {
+        shader: STARS_SHADER.to_string(),
+        seed: seed.background(),
+    });
+
+    commands.entity(root_entity.world).with_child((
+        Camera3d::default(),
+        Camera {
+            hdr: true,
+            clear_color: ClearColorConfig::None,
+            ..Default::default()
+        },
+        RenderBackground,
+        Msaa::Off,
+        Bloom::NATURAL,
+        DebandDither::Enabled,
+    ));
+
+    let mut preloaded = PreloadedAssets {
+        buildings: asset_server.load(DEFAULT_BUILDINGS),
+        ..Default::default()
+    };
+    let buildings = preloaded.buildings.clone().untyped();
+    preloaded.add(DEFAULT_BUILDINGS, buildings);
+    for (path, materials) in models() {
+        preloaded
+            .add(path, asset_server.load::<Gltf>(path).untyped())
+            .materials = materials;
+    }
+    for path in shaders() {
+        preloaded.add(path, asset_server.load::<Shader>(path).untyped());
+    }
+    preloaded.add(
+        MAIN_MENU_MUSIC,
+        asset_server.load::<AudioSource>(MAIN_MENU_MUSIC).untyped(),
+    );
+    preloaded
+        .add("music", asset_server.load_folder("music").untyped())
+        .optional = true;
+    commands.insert_resource(preloaded);
+
+    let bar = commands
+        .spawn((
+            Node {
+                width: Val::Percent(0.0),
+                height: Val::Percent(100.0),
+                ..Default::default()
+            },
+            BackgroundColor(COLOR_POWER_LOW),
+        ))
+        .id();
+    let status = commands
+        .spawn((
+            Text::default(),
+            TextLayout::new_with_justify(JustifyText::Center),
+        ))
+        .id();
+    commands.insert_resource(Entities { bar, status });
+
+    commands.entity(root_entity.ui).with_children(|root| {
+        root.spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(15.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Avaruus"),
+                TextFont {
+                    font_size: 64.0,
+                    ..Default::default()
+                },
+            ));
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(600.0),
+                        height: Val::Px(30.0),
+                        ..Default::default()
+                    },
+                    Outline {
+                        color: COLOR_HIGHLIGHT_DARK,
+                        offset: Val::ZERO,
+                        width: Val::Px(3.0),
+                    },
+                    BackgroundColor(Color::BLACK),
+                ))
+                .add_child(bar);
+        })
+        .add_child(status);
+    });
+}
+
+fn update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut preloaded: ResMut<PreloadedAssets>,
+    entities: Res<Entities>,
+    defs: Res<Assets<BuildingDefs>>,
+    gltfs: Res<Assets<Gltf>>,
+    mut backgrounds: Query<(&mut Node, &mut BackgroundColor)>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // Thumbnails and custom models are only known once the building list is loaded
+    if !preloaded.thumbnails {
+        if let Some(defs) = defs.get(&preloaded.buildings) {
+            let defs = defs
+                .buildings
+                .iter()
+                .map(|def| (def.thumbnail.clone(), def.model.clone()))
+                .collect::<Vec<_>>();
+            for (thumbnail, model) in defs {
+                preloaded.add(&thumbnail, asset_server.load::<Image>(&thumbnail).untyped());
+                if let Some(model) = model {
+                    preloaded.add(&model, asset_server.load::<Gltf>(&model).untyped());
+                }
+            }
+            preloaded.thumbnails = true;
+        }
+    }
+
+    let mut errors = vec![];
+    for preload in preloaded.assets.iter_mut().filter(|p| !p.done) {
+        let id = preload.handle.id();
+        if asset_server
+            .get_recursive_dependency_load_state(id)
+            .is_some_and(|state| state.is_failed())
+        {
+            preload.done = true;
+            if !preload.optional {
+                errors.push(format!("Failed to load {}", preload.path));
+            }
+            continue;
+        }
+        if !asset_server.is_loaded_with_dependencies(id) {
+            continue;
+        }
+        preload.done = true;
+
+        // Thumbnails are drawn at a fraction of their size
+        if let Ok(handle) = preload.handle.clone().try_typed::<Image>() {
+            commands.spawn(GenerateMips::new(handle.id(), GenerateMipsMode::Once));
+            continue;
+        }
+
+        let Ok(handle) = preload.handle.clone().try_typed::<Gltf>() else {
+            continue;
+        };
+        if let Some(gltf) = gltfs.get(&handle) {
+            for material in preload.materials {
+                if !gltf.named_materials.contains_key(*material) {
+                    errors.push(format!(
+                        "{} has no material named \"{material}\"",
+                        preload.path
+                    ));
+                }
+            }
+        }
+    }
+    for error in &errors {
+        error!("{error}");
+    }
+    preloaded.errors.extend(errors);
+
+    let total = preloaded.assets.len() + !preloaded.thumbnails as usize;
+    let done = preloaded.assets.iter().filter(|p| p.done).count();
+    let progress = done as f32 / total as f32;
+
+    if let Ok((mut node, mut color)) = backgrounds.get_mut(entities.bar) {
+        node.width = Val::Percent(progress * 100.0);
+        color.0 = COLOR_POWER_LOW.mix(&COLOR_POWER_HIGH, progress);
+    }
+
+    if let Ok((mut text, mut color)) = texts.get_mut(entities.status) {
+        if preloaded.errors.is_empty() {
+            text.0 = format!("Loading {done}/{total}");
+        } else {
+            text.0 = preloaded.errors.join("\n");
+            color.0 = Color::srgb(1.0, 0.3, 0.2);
+        }
+    }
+
+    if done == total && preloaded.errors.is_empty() {
+        next_state.set(AppState::MainMenu);
+    }
+}
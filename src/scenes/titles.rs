@@ -0,0 +1,114 @@
+use bevy::{
+    audio::PlaybackMode,
+    core_pipeline::{bloom::Bloom, tonemapping::DebandDither},
+    prelude::*,
+};
+
+use crate::components::{
+    background::{BackgroundPluginSettings, RenderBackground},
+    game_button::GameButton,
+    mouse_event::Clicked,
+    sfx::AudioChannel,
+};
+
+use super::{
+    game::{seed::GameSeed, GameClock, RunStats},
+    main_menu::STARS_SHADER,
+    AppSceneRoot, AppState,
+};
+
+pub struct TitlesPlugin;
+
+impl Plugin for TitlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Titles), setup);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    root_entity: Res<AppSceneRoot>,
+    asset_server: Res<AssetServer>,
+    clock: Res<GameClock>,
+    run_stats: Res<RunStats>,
+    seed: Res<GameSeed>,
+) {
+    commands.insert_resource(BackgroundPluginSettings {
+        shader: STARS_SHADER.to_string(),
+        seed: seed.background(),
+    });
+
+    commands
+        .entity(root_entity.world)
+        .with_child((
+            Camera3d::default(),
+            Camera {
+                hdr: true,
+                clear_color: ClearColorConfig::None,
+                ..Default::default()
+            },
+            RenderBackground,
+            Msaa::Off,
+            Bloom::NATURAL,
+            DebandDither::Enabled,
+        ))
+        .with_child((
+            AudioChannel::Music,
+            AudioPlayer::new(asset_server.load("Cojam - Milky Main Menu.ogg")),
+            PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                ..Default::default()
+            },
+        ));
+
+    let elapsed = clock.0 as u32;
+    let stats = format!(
+        "Time: {:02}:{:02}:{:02}\nRocks harvested: {}\nRooms built: {}",
+        elapsed / 3600,
+        elapsed / 60 % 60,
+        elapsed % 60,
+        run_stats.rocks_harvested,
+        run_stats.rooms_built,
+    );
+
+    let spacer = Node {
+        height: Val::Px(5.0),
+        ..Default::default()
+    };
+    let back = commands
+        .spawn(GameButton::new("Main menu", 200.0))
+        .observe(
+            |_: Trigger<Clicked>, mut next: ResMut<NextState<AppState>>| {
+                next.set(AppState::MainMenu)
+            },
+        )
+        .id();
+    commands.entity(root_entity.ui).with_children(|root| {
+        root.spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(15.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        })
+        .with_child((
+            Text::new("Warp jump complete"),
+            TextFont {
+                font_size: 64.0,
+                ..Default::default()
+            },
+        ))
+        .with_child(Text::new(
+            "You made it to the nearest base. The long sleep is over.",
+        ))
+        .with_child(spacer.clone())
+        .with_child((
+            Text::new(stats),
+            TextLayout::new_with_justify(JustifyText::Center),
+        ))
+        .with_child(spacer.clone())
+        .add_children(&[back]);
+    });
+}